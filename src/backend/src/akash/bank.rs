@@ -1,29 +1,26 @@
 use cosmrs::{
-    auth::BaseAccount,
     bank::MsgSend,
     crypto::PublicKey,
     proto::cosmos::{
         bank::v1beta1::{QueryBalanceRequest, QueryBalanceResponse},
         base::v1beta1::Coin as CoinProto,
     },
-    tx::{Fee, Msg},
+    tx::Msg,
     AccountId, Coin, Denom,
 };
 use prost::Message;
 use std::str::FromStr;
 
-use crate::helpers::EcdsaKeyIds;
+use super::{address::get_account_id_from_public_key, tx::TxMessage};
 
-use super::{address::get_account_id_from_public_key, tx::create_tx};
+/// the actual gas limit is estimated by simulating the transaction
+const MAX_GAS_SEND: u64 = 100_000;
 
-pub async fn create_send_tx(
+pub fn create_send_msg(
     sender_public_key: &PublicKey,
     recipient_account_id: AccountId,
     amount: u64,
-    account: &BaseAccount,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
-) -> Result<Vec<u8>, String> {
+) -> Result<TxMessage, String> {
     let amount = Coin {
         amount: amount.into(),
         denom: Denom::from_str("uakt").unwrap(),
@@ -35,25 +32,10 @@ pub async fn create_send_tx(
         amount: vec![amount.clone()],
     };
 
-    let gas = 100_000u64;
-    let fee = Fee::from_amount_and_gas(
-        Coin {
-            amount: 3_000u128,
-            denom: Denom::from_str("uakt").unwrap(),
-        },
-        gas,
-    );
-
-    create_tx(
-        sender_public_key,
-        msg_send.to_any().unwrap(),
-        fee,
-        account.sequence,
-        account.account_number,
-        ecdsa_key,
-        chain_id,
-    )
-    .await
+    Ok(TxMessage {
+        msg: msg_send.to_any().unwrap(),
+        max_gas: MAX_GAS_SEND,
+    })
 }
 
 pub async fn get_balance(rpc_url: String, public_key: &PublicKey) -> Result<CoinProto, String> {
@@ -1,45 +1,318 @@
-use cosmrs::{proto::cosmos::base::query::v1beta1::PageRequest, AccountId};
+use cosmrs::{
+    proto::cosmos::base::{query::v1beta1::PageRequest, v1beta1::DecCoin},
+    AccountId,
+};
 use prost::Message;
 
-use super::proto::market::{
-    bid::BidFilters,
-    query::{QueryBidResponse, QueryBidsRequest, QueryBidsResponse},
+use crate::api::{
+    log_warn, BidPrice, BidRanking, BidResourceOffer, BidSelectionStrategy, DeploymentBid,
+};
+
+use super::{
+    proto::{
+        base::resourcevalue::ResourceValue,
+        market::{
+            bid::{Bid, BidFilters, BidId, ResourceOffer},
+            query::{QueryBidRequest, QueryBidResponse, QueryBidsRequest, QueryBidsResponse},
+        },
+    },
+    provider::{fetch_provider, fetch_provider_audits},
 };
 
+/// keep the pages small, as each bid carries the offered resources
+/// and the response must fit in the HTTPS outcall response limit
+const BIDS_PAGE_LIMIT: u64 = 5;
+/// upper bound on the pages fetched, to limit the number of HTTPS outcalls
+const MAX_BIDS_PAGES: usize = 10;
+/// the amount of a [DecCoin] is encoded as an integer with 18 decimals
+const DEC_COIN_PRECISION: i32 = 18;
+
+/// Bid state of the open bids, waiting to be accepted.
+pub const OPEN_BID_STATE: &str = "open";
+/// Bid state of the accepted bids, for which a lease has been created.
+pub const ACTIVE_BID_STATE: &str = "active";
+
+/// Fetches all the bids in the given state for the deployment, on all its orders, following the pagination.
 pub async fn fetch_bids(
     rpc_url: String,
     account_id: &AccountId,
     dseq: u64,
+    state: &str,
 ) -> Result<Vec<QueryBidResponse>, String> {
-    let query = QueryBidsRequest {
-        filters: Some(BidFilters {
-            owner: account_id.to_string(),
-            dseq, // same as in the CreateDeployment transaction
-            gseq: 1,
-            oseq: 1,
-            provider: "".to_string(),
-            state: "".to_string(),
-        }),
-        pagination: Some(PageRequest {
-            key: vec![],
-            limit: 1,
-            offset: 0,
-            count_total: false,
-            reverse: false,
-        }),
-    };
+    let mut bids = vec![];
+    let mut next_key = vec![];
+
+    for _ in 0..MAX_BIDS_PAGES {
+        let query = QueryBidsRequest {
+            filters: Some(BidFilters {
+                owner: account_id.to_string(),
+                dseq, // same as in the CreateDeployment transaction
+                // 0 matches any group and order
+                gseq: 0,
+                oseq: 0,
+                provider: "".to_string(),
+                state: state.to_string(),
+            }),
+            pagination: Some(PageRequest {
+                key: next_key,
+                limit: BIDS_PAGE_LIMIT,
+                offset: 0,
+                count_total: false,
+                reverse: false,
+            }),
+        };
+
+        let abci_res = ic_tendermint_rpc::abci_query(
+            rpc_url.clone(),
+            Some(String::from("/akash.market.v1beta4.Query/Bids")),
+            query.encode_to_vec(),
+            None,
+            false,
+        )
+        .await?;
+
+        if abci_res.response.code.is_err() {
+            return Err(format!("could not fetch bids: {}", abci_res.response.log));
+        }
+
+        let res = QueryBidsResponse::decode(abci_res.response.value.as_slice())
+            .map_err(|e| e.to_string())?;
+
+        bids.extend(res.bids);
+
+        next_key = res.pagination.map(|p| p.next_key).unwrap_or_default();
+        if next_key.is_empty() {
+            break;
+        }
+    }
+
+    Ok(bids)
+}
+
+/// Fetches the bid with the given id, `None` if it does not exist.
+pub async fn fetch_bid(rpc_url: String, bid_id: BidId) -> Result<Option<Bid>, String> {
+    let query = QueryBidRequest { id: Some(bid_id) };
 
     let abci_res = ic_tendermint_rpc::abci_query(
         rpc_url,
-        Some(String::from("/akash.market.v1beta4.Query/Bids")),
+        Some(String::from("/akash.market.v1beta4.Query/Bid")),
         query.encode_to_vec(),
         None,
         false,
     )
     .await?;
 
+    if abci_res.response.code.is_err() {
+        if abci_res.response.log.contains("not found") {
+            return Ok(None);
+        }
+
+        return Err(format!("could not fetch bid: {}", abci_res.response.log));
+    }
+
     let res =
-        QueryBidsResponse::decode(abci_res.response.value.as_slice()).map_err(|e| e.to_string())?;
+        QueryBidResponse::decode(abci_res.response.value.as_slice()).map_err(|e| e.to_string())?;
+
+    Ok(res.bid)
+}
+
+/// Returns the (gseq, oseq) of the order the bid was placed on.
+pub fn bid_order(bid: &QueryBidResponse) -> Option<(u32, u32)> {
+    bid.bid
+        .as_ref()
+        .and_then(|b| b.bid_id.as_ref())
+        .map(|bid_id| (bid_id.gseq, bid_id.oseq))
+}
+
+/// The bid chosen by [select_bid], together with the reason why it was chosen.
+#[derive(Clone, Debug)]
+pub struct SelectedBid {
+    pub bid: Bid,
+    pub reason: String,
+}
+
+/// Selects the bid to accept according to the given strategy.
+///
+/// Bids are ranked first and then checked one by one against the provider requirements,
+/// so that the provider related queries are made only until an eligible bid is found.
+///
+/// Returns `None` if no bid satisfies the strategy.
+pub async fn select_bid(
+    rpc_url: String,
+    bids: Vec<QueryBidResponse>,
+    strategy: &BidSelectionStrategy,
+) -> Result<Option<SelectedBid>, String> {
+    let mut candidates = vec![];
+    for bid in bids.into_iter().filter_map(|b| b.bid) {
+        let Some(bid_id) = bid.bid_id.as_ref() else {
+            continue;
+        };
+
+        if !strategy.providers.allows(&bid_id.provider) {
+            continue;
+        }
+
+        // a malformed bid must not prevent the other bids from being selected
+        let price = match bid_price(&bid) {
+            Ok(price) => price,
+            Err(e) => {
+                log_warn!(
+                    format!(
+                        "Skipping bid of provider {} on order {}/{}: {}",
+                        bid_id.provider, bid_id.gseq, bid_id.oseq, e
+                    ),
+                    "select_bid"
+                );
+                continue;
+            }
+        };
+        candidates.push((price, bid));
+    }
+
+    match strategy.ranking {
+        BidRanking::LowestPrice => candidates.sort_by(|(a, _), (b, _)| a.total_cmp(b)),
+        BidRanking::FirstReceived => candidates.sort_by_key(|(_, bid)| bid.created_at),
+    }
+
+    for (price, bid) in candidates {
+        // SAFETY: bids without id have been filtered out above
+        let provider = bid.bid_id.as_ref().unwrap().provider.clone();
+
+        if !provider_has_attributes(rpc_url.clone(), &provider, strategy).await? {
+            continue;
+        }
+
+        if !provider_is_audited(rpc_url.clone(), &provider, strategy).await? {
+            continue;
+        }
+
+        let reason = format!(
+            "provider {} selected by {:?} ranking with price {} uakt/block (providers filter: {:?}, required attributes: {:?}, min audit signatures: {})",
+            provider,
+            strategy.ranking,
+            price,
+            strategy.providers,
+            strategy.required_attributes,
+            strategy.min_audit_signatures,
+        );
+
+        return Ok(Some(SelectedBid { bid, reason }));
+    }
+
+    Ok(None)
+}
+
+/// Price of the bid in uakt per block.
+pub fn bid_price(bid: &Bid) -> Result<f64, String> {
+    let price = bid.price.as_ref().ok_or(String::from("bid has no price"))?;
+
+    dec_coin_amount(price)
+}
+
+/// Parses the amount of a [DecCoin], which may be encoded either
+/// as a decimal string or as an integer with 18 decimals.
+pub fn dec_coin_amount(coin: &DecCoin) -> Result<f64, String> {
+    let amount = coin
+        .amount
+        .parse::<f64>()
+        .map_err(|e| format!("could not parse amount {}: {:?}", coin.amount, e))?;
+
+    if coin.amount.contains('.') {
+        Ok(amount)
+    } else {
+        Ok(amount / 10_f64.powi(DEC_COIN_PRECISION))
+    }
+}
+
+async fn provider_has_attributes(
+    rpc_url: String,
+    provider: &str,
+    strategy: &BidSelectionStrategy,
+) -> Result<bool, String> {
+    if !strategy.requires_provider_attributes() {
+        return Ok(true);
+    }
+
+    let provider = fetch_provider(rpc_url, provider.to_string()).await?;
+
+    Ok(strategy.required_attributes.iter().all(|(key, value)| {
+        provider
+            .attributes
+            .iter()
+            .any(|attr| &attr.key == key && &attr.value == value)
+    }))
+}
+
+async fn provider_is_audited(
+    rpc_url: String,
+    provider: &str,
+    strategy: &BidSelectionStrategy,
+) -> Result<bool, String> {
+    if !strategy.requires_audit() {
+        return Ok(true);
+    }
+
+    let mut auditors = fetch_provider_audits(rpc_url, provider.to_string())
+        .await?
+        .into_iter()
+        .filter(|audit| !audit.attributes.is_empty())
+        .map(|audit| audit.auditor)
+        .filter(|auditor| strategy.auditors.is_empty() || strategy.auditors.contains(auditor))
+        .collect::<Vec<_>>();
+    auditors.sort();
+    auditors.dedup();
+
+    Ok(auditors.len() >= strategy.min_audit_signatures as usize)
+}
+
+impl TryFrom<Bid> for DeploymentBid {
+    type Error = String;
+
+    fn try_from(bid: Bid) -> Result<Self, Self::Error> {
+        let bid_id = bid.bid_id.clone().ok_or(String::from("bid has no id"))?;
+        let price = bid.price.clone().ok_or(String::from("bid has no price"))?;
+
+        Ok(DeploymentBid {
+            provider: bid_id.provider,
+            gseq: bid_id.gseq,
+            oseq: bid_id.oseq,
+            price: BidPrice {
+                amount: dec_coin_amount(&price)?,
+                denom: price.denom,
+            },
+            created_at: bid.created_at,
+            resources_offered: bid
+                .resources_offered
+                .iter()
+                .map(BidResourceOffer::from)
+                .collect(),
+        })
+    }
+}
+
+impl From<&ResourceOffer> for BidResourceOffer {
+    fn from(offer: &ResourceOffer) -> Self {
+        let resources = offer.resources.clone().unwrap_or_default();
+
+        BidResourceOffer {
+            count: offer.count,
+            cpu_millis: resource_value(resources.cpu.and_then(|cpu| cpu.units)),
+            memory_bytes: resource_value(resources.memory.and_then(|memory| memory.quantity)),
+            storage_bytes: resources
+                .storage
+                .into_iter()
+                .map(|storage| resource_value(storage.quantity))
+                .sum(),
+            gpu_units: resource_value(resources.gpu.and_then(|gpu| gpu.units)),
+        }
+    }
+}
 
-    Ok(res.bids)
+/// Resource values are encoded as decimal strings, unparsable values are counted as 0.
+fn resource_value(value: Option<ResourceValue>) -> u64 {
+    value
+        .and_then(|v| String::from_utf8(v.val).ok())
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|v| v as u64)
+        .unwrap_or_default()
 }
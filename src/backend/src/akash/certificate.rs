@@ -1,20 +1,14 @@
-use std::str::FromStr;
-
-use cosmrs::{
-    auth::BaseAccount,
-    crypto::PublicKey,
-    tx::{Fee, Msg},
-    AccountId, Coin, Denom, ErrorReport,
-};
-
-use crate::helpers::EcdsaKeyIds;
+use cosmrs::{crypto::PublicKey, tx::Msg, AccountId, ErrorReport};
 
 use super::{
     address::get_account_id_from_public_key,
     proto::{self},
-    tx::create_tx,
+    tx::TxMessage,
 };
 
+/// the actual gas limit is estimated by simulating the transaction
+const MAX_GAS_CREATE_CERTIFICATE: u64 = 100_000;
+
 /// MsgCreateCertificate defines an SDK message for creating certificate.
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub struct MsgCreateCertificate {
@@ -67,36 +61,19 @@ impl From<&MsgCreateCertificate> for proto::cert::MsgCreateCertificate {
     }
 }
 
-pub async fn create_certificate_tx(
+pub fn create_certificate_msg(
     sender_public_key: &PublicKey,
     cert_pem: Vec<u8>,
     pub_key_pem: Vec<u8>,
-    account: &BaseAccount,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
-) -> Result<Vec<u8>, String> {
+) -> Result<TxMessage, String> {
     let msg = MsgCreateCertificate {
         owner: get_account_id_from_public_key(sender_public_key)?,
         cert: cert_pem,
         pubkey: pub_key_pem,
     };
 
-    let amount = Coin {
-        amount: 3_000u128,
-        denom: Denom::from_str("uakt").unwrap(),
-    };
-
-    let gas = 100_000u64;
-    let fee = Fee::from_amount_and_gas(amount, gas);
-
-    create_tx(
-        sender_public_key,
-        msg.to_any().unwrap(),
-        fee,
-        account.sequence,
-        account.account_number,
-        ecdsa_key,
-        chain_id,
-    )
-    .await
+    Ok(TxMessage {
+        msg: msg.to_any().unwrap(),
+        max_gas: MAX_GAS_CREATE_CERTIFICATE,
+    })
 }
@@ -1,30 +1,39 @@
 use std::str::FromStr;
 
-use cosmrs::{auth::BaseAccount, crypto::PublicKey, tx::Fee, Any, Coin, Denom};
+use cosmrs::{crypto::PublicKey, proto::cosmos::base::v1beta1::DecCoin, Any, Coin, Denom};
+use prost::Message;
 
-use crate::helpers::EcdsaKeyIds;
+use crate::api::EscrowSnapshot;
 
 use super::{
     address::get_account_id_from_public_key,
-    proto::deployment::{
-        deploymentmsg::{
-            MsgCloseDeployment, MsgCreateDeployment, MsgDepositDeployment, MsgUpdateDeployment,
+    bids::dec_coin_amount,
+    proto::{
+        deployment::{
+            deploymentmsg::{
+                MsgCloseDeployment, MsgCreateDeployment, MsgDepositDeployment, MsgUpdateDeployment,
+            },
+            query::{QueryDeploymentRequest, QueryDeploymentResponse},
+            DeploymentID,
         },
-        DeploymentID,
+        escrow::types::Account,
     },
     sdl::SdlV3,
-    tx::create_tx,
+    tx::TxMessage,
 };
 
-pub async fn create_deployment_tx(
+/// maximum gas of each message, the actual gas limit is estimated by simulating the transaction
+const MAX_GAS_CREATE_DEPLOYMENT: u64 = 800_000;
+const MAX_GAS_DEPOSIT_DEPLOYMENT: u64 = 800_000;
+const MAX_GAS_UPDATE_DEPLOYMENT: u64 = 800_000;
+const MAX_GAS_CLOSE_DEPLOYMENT: u64 = 1_500_000;
+
+pub fn create_deployment_msg(
     sender_public_key: &PublicKey,
     sdl: &SdlV3,
     dseq: u64,
     deposit_uakt_amount: u64,
-    account: &BaseAccount,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
-) -> Result<Vec<u8>, String> {
+) -> Result<TxMessage, String> {
     let account_id = get_account_id_from_public_key(sender_public_key)?.to_string();
     // see https://github.com/akash-network/cloudmos/blob/8a8098b7e371e801dad3aad81ef92b8dfe387e4c/deploy-web/src/utils/deploymentData/v1beta3.ts#L230
     let msg = MsgCreateDeployment {
@@ -45,36 +54,17 @@ pub async fn create_deployment_tx(
         depositor: account_id,
     };
 
-    let amount = Coin {
-        amount: 20_000u128,
-        denom: Denom::from_str("uakt").unwrap(),
-    };
-
-    let gas = 800_000u64;
-    let fee = Fee::from_amount_and_gas(amount, gas);
-
-    let tx_raw = create_tx(
-        sender_public_key,
-        Any::from_msg(&msg).unwrap(),
-        fee,
-        account.sequence,
-        account.account_number,
-        ecdsa_key,
-        chain_id,
-    )
-    .await?;
-
-    Ok(tx_raw)
+    Ok(TxMessage {
+        msg: Any::from_msg(&msg).unwrap(),
+        max_gas: MAX_GAS_CREATE_DEPLOYMENT,
+    })
 }
 
-pub async fn deposit_deployment_tx(
+pub fn deposit_deployment_msg(
     sender_public_key: &PublicKey,
     dseq: u64,
     uakt_amount: u64,
-    account: &BaseAccount,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
-) -> Result<Vec<u8>, String> {
+) -> Result<TxMessage, String> {
     let account_id = get_account_id_from_public_key(sender_public_key)?.to_string();
     let msg = MsgDepositDeployment {
         id: Some(DeploymentID {
@@ -92,36 +82,17 @@ pub async fn deposit_deployment_tx(
         depositor: account_id,
     };
 
-    let amount = Coin {
-        amount: 20_000u128,
-        denom: Denom::from_str("uakt").unwrap(),
-    };
-
-    let gas = 800_000u64;
-    let fee = Fee::from_amount_and_gas(amount, gas);
-
-    let tx_raw = create_tx(
-        sender_public_key,
-        Any::from_msg(&msg).unwrap(),
-        fee,
-        account.sequence,
-        account.account_number,
-        ecdsa_key,
-        chain_id,
-    )
-    .await?;
-
-    Ok(tx_raw)
+    Ok(TxMessage {
+        msg: Any::from_msg(&msg).unwrap(),
+        max_gas: MAX_GAS_DEPOSIT_DEPLOYMENT,
+    })
 }
 
-pub async fn update_deployment_sdl_tx(
+pub fn update_deployment_sdl_msg(
     sender_public_key: &PublicKey,
     sdl: &SdlV3,
     dseq: u64,
-    account: &BaseAccount,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
-) -> Result<Vec<u8>, String> {
+) -> Result<TxMessage, String> {
     let account_id = get_account_id_from_public_key(sender_public_key)?.to_string();
     let msg = MsgUpdateDeployment {
         id: Some(DeploymentID {
@@ -133,35 +104,13 @@ pub async fn update_deployment_sdl_tx(
         version: sdl.manifest_version(),
     };
 
-    let amount = Coin {
-        amount: 20_000u128,
-        denom: Denom::from_str("uakt").unwrap(),
-    };
-
-    let gas = 800_000u64;
-    let fee = Fee::from_amount_and_gas(amount, gas);
-
-    let tx_raw = create_tx(
-        sender_public_key,
-        Any::from_msg(&msg).unwrap(),
-        fee,
-        account.sequence,
-        account.account_number,
-        ecdsa_key,
-        chain_id,
-    )
-    .await?;
-
-    Ok(tx_raw)
+    Ok(TxMessage {
+        msg: Any::from_msg(&msg).unwrap(),
+        max_gas: MAX_GAS_UPDATE_DEPLOYMENT,
+    })
 }
 
-pub async fn close_deployment_tx(
-    sender_public_key: &PublicKey,
-    dseq: u64,
-    account: &BaseAccount,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
-) -> Result<Vec<u8>, String> {
+pub fn close_deployment_msg(sender_public_key: &PublicKey, dseq: u64) -> Result<TxMessage, String> {
     let msg = MsgCloseDeployment {
         id: Some(DeploymentID {
             owner: get_account_id_from_public_key(sender_public_key)?.to_string(),
@@ -169,22 +118,55 @@ pub async fn close_deployment_tx(
         }),
     };
 
-    let amount = Coin {
-        amount: 40_000u128,
-        denom: Denom::from_str("uakt").unwrap(),
+    Ok(TxMessage {
+        msg: Any::from_msg(&msg).unwrap(),
+        max_gas: MAX_GAS_CLOSE_DEPLOYMENT,
+    })
+}
+
+/// Fetches the deployment from the chain.
+///
+/// Returns `None` if the deployment does not exist.
+pub async fn fetch_deployment(
+    rpc_url: String,
+    owner: String,
+    dseq: u64,
+) -> Result<Option<QueryDeploymentResponse>, String> {
+    let query = QueryDeploymentRequest {
+        id: Some(DeploymentID { owner, dseq }),
     };
 
-    let gas = 1_500_000u64;
-    let fee = Fee::from_amount_and_gas(amount, gas);
-
-    create_tx(
-        sender_public_key,
-        Any::from_msg(&msg).unwrap(),
-        fee,
-        account.sequence,
-        account.account_number,
-        ecdsa_key,
-        chain_id,
+    let abci_res = ic_tendermint_rpc::abci_query(
+        rpc_url,
+        Some(String::from("/akash.deployment.v1beta3.Query/Deployment")),
+        query.encode_to_vec(),
+        None,
+        false,
     )
-    .await
+    .await?;
+
+    if abci_res.response.code.is_err() {
+        if abci_res.response.log.contains("not found") {
+            return Ok(None);
+        }
+
+        return Err(format!(
+            "could not query deployment: {}",
+            abci_res.response.log
+        ));
+    }
+
+    let res = QueryDeploymentResponse::decode(abci_res.response.value.as_slice())
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(res))
+}
+
+pub fn escrow_snapshot(account: &Account) -> Result<EscrowSnapshot, String> {
+    let amount = |coin: &Option<DecCoin>| coin.as_ref().map_or(Ok(0.0), dec_coin_amount);
+
+    Ok(EscrowSnapshot {
+        balance_uakt: amount(&account.balance)?,
+        transferred_uakt: amount(&account.transferred)?,
+    })
 }
@@ -0,0 +1,67 @@
+use ic_tendermint_rpc::{ExecTxResult, TxEvent};
+
+use super::proto::{deployment::DeploymentID, market::lease::LeaseId};
+
+/// kind of the events emitted by the Akash modules
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/go/sdkutil/event.go
+const AKASH_EVENT_KIND: &str = "akash.v1";
+
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/go/node/deployment/v1beta3/event.go
+const DEPLOYMENT_CREATED_ACTION: &str = "deployment-created";
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/go/node/market/v1beta4/event.go
+const LEASE_CREATED_ACTION: &str = "lease-created";
+
+/// Id of the deployment created by the transaction, read from its events.
+pub fn created_deployment_id(result: &ExecTxResult) -> Result<DeploymentID, String> {
+    let event = akash_event(result, DEPLOYMENT_CREATED_ACTION)?;
+
+    Ok(DeploymentID {
+        owner: attribute(event, "owner")?.to_string(),
+        dseq: parse_attribute(event, "dseq")?,
+    })
+}
+
+/// Ids of the leases created by the transaction, read from its events.
+pub fn created_lease_ids(result: &ExecTxResult) -> Result<Vec<LeaseId>, String> {
+    akash_events(result, LEASE_CREATED_ACTION)
+        .map(|event| {
+            Ok(LeaseId {
+                owner: attribute(event, "owner")?.to_string(),
+                dseq: parse_attribute(event, "dseq")?,
+                gseq: parse_attribute(event, "gseq")?,
+                oseq: parse_attribute(event, "oseq")?,
+                provider: attribute(event, "provider")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn akash_events<'a>(
+    result: &'a ExecTxResult,
+    action: &'a str,
+) -> impl Iterator<Item = &'a TxEvent> + 'a {
+    result.events.iter().filter(move |event| {
+        event.kind == AKASH_EVENT_KIND && event.attribute("action") == Some(action)
+    })
+}
+
+fn akash_event<'a>(result: &'a ExecTxResult, action: &'a str) -> Result<&'a TxEvent, String> {
+    akash_events(result, action)
+        .next()
+        .ok_or(format!("no {} event in tx {}", action, result.hash))
+}
+
+fn attribute<'a>(event: &'a TxEvent, key: &str) -> Result<&'a str, String> {
+    event
+        .attribute(key)
+        .ok_or(format!("{} event has no {} attribute", event.kind, key))
+}
+
+fn parse_attribute<T: std::str::FromStr>(event: &TxEvent, key: &str) -> Result<T, String> {
+    let value = attribute(event, key)?;
+
+    value
+        .parse()
+        .map_err(|_| format!("could not parse {} attribute: {}", key, value))
+}
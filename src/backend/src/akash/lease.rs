@@ -1,42 +1,136 @@
-use std::str::FromStr;
-
-use cosmrs::{auth::BaseAccount, crypto::PublicKey, tx::Fee, Coin, Denom};
+use cosmrs::proto::cosmos::base::query::v1beta1::PageRequest;
+use prost::Message;
 use prost_types::Any;
 
-use crate::helpers::EcdsaKeyIds;
+use crate::api::{DeploymentLease, LeaseState};
 
 use super::{
-    proto::market::{bid::BidId, lease::MsgCreateLease},
-    tx::create_tx,
+    bids::dec_coin_amount,
+    proto::market::{
+        bid::BidId,
+        lease::{Lease, LeaseFilters, LeaseId, MsgCreateLease, State},
+        query::{QueryLeaseRequest, QueryLeaseResponse, QueryLeasesRequest, QueryLeasesResponse},
+    },
+    tx::TxMessage,
 };
 
-pub async fn create_lease_tx(
-    sender_public_key: &PublicKey,
-    bid_id: BidId,
-    account: &BaseAccount,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
-) -> Result<Vec<u8>, String> {
+/// a deployment has at most one lease for each of its orders
+const LEASES_PAGE_LIMIT: u64 = 100;
+/// the actual gas limit is estimated by simulating the transaction
+const MAX_GAS_CREATE_LEASE: u64 = 2_500_000;
+
+pub fn create_lease_msg(bid_id: BidId) -> Result<TxMessage, String> {
     let msg = MsgCreateLease {
         bid_id: Some(bid_id),
     };
 
-    let amount = Coin {
-        amount: 65_000u128,
-        denom: Denom::from_str("uakt").unwrap(),
+    Ok(TxMessage {
+        msg: Any::from_msg(&msg).unwrap(),
+        max_gas: MAX_GAS_CREATE_LEASE,
+    })
+}
+
+/// Fetches the lease with the given id, `None` if it does not exist.
+pub async fn fetch_lease(
+    rpc_url: String,
+    lease_id: LeaseId,
+) -> Result<Option<QueryLeaseResponse>, String> {
+    let query = QueryLeaseRequest { id: Some(lease_id) };
+
+    let abci_res = ic_tendermint_rpc::abci_query(
+        rpc_url,
+        Some(String::from("/akash.market.v1beta4.Query/Lease")),
+        query.encode_to_vec(),
+        None,
+        false,
+    )
+    .await?;
+
+    if abci_res.response.code.is_err() {
+        if abci_res.response.log.contains("not found") {
+            return Ok(None);
+        }
+
+        return Err(format!("could not fetch lease: {}", abci_res.response.log));
+    }
+
+    let res = QueryLeaseResponse::decode(abci_res.response.value.as_slice())
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(res))
+}
+
+/// Fetches the leases of the deployment, in any state.
+pub async fn fetch_leases(
+    rpc_url: String,
+    owner: String,
+    dseq: u64,
+) -> Result<Vec<QueryLeaseResponse>, String> {
+    let query = QueryLeasesRequest {
+        filters: Some(LeaseFilters {
+            owner,
+            dseq,
+            // 0 matches any group and order
+            gseq: 0,
+            oseq: 0,
+            provider: "".to_string(),
+            state: "".to_string(),
+        }),
+        pagination: Some(PageRequest {
+            key: vec![],
+            limit: LEASES_PAGE_LIMIT,
+            offset: 0,
+            count_total: false,
+            reverse: false,
+        }),
     };
 
-    let gas = 2_500_000u64;
-    let fee = Fee::from_amount_and_gas(amount, gas);
-
-    create_tx(
-        sender_public_key,
-        Any::from_msg(&msg).unwrap(),
-        fee,
-        account.sequence,
-        account.account_number,
-        ecdsa_key,
-        chain_id,
+    let abci_res = ic_tendermint_rpc::abci_query(
+        rpc_url,
+        Some(String::from("/akash.market.v1beta4.Query/Leases")),
+        query.encode_to_vec(),
+        None,
+        false,
     )
-    .await
+    .await?;
+
+    if abci_res.response.code.is_err() {
+        return Err(format!("could not fetch leases: {}", abci_res.response.log));
+    }
+
+    let res = QueryLeasesResponse::decode(abci_res.response.value.as_slice())
+        .map_err(|e| e.to_string())?;
+
+    Ok(res.leases)
+}
+
+impl TryFrom<Lease> for DeploymentLease {
+    type Error = String;
+
+    fn try_from(lease: Lease) -> Result<Self, Self::Error> {
+        let lease_id = lease.lease_id.ok_or(String::from("lease has no id"))?;
+        let price = lease.price.ok_or(String::from("lease has no price"))?;
+
+        Ok(DeploymentLease {
+            provider: lease_id.provider,
+            gseq: lease_id.gseq,
+            oseq: lease_id.oseq,
+            state: LeaseState::from(lease.state),
+            price_per_block_uakt: dec_coin_amount(&price)?,
+            created_at: lease.created_at,
+            // the block height is 0 until the lease is closed
+            closed_on: (lease.closed_on > 0).then_some(lease.closed_on),
+        })
+    }
+}
+
+impl From<i32> for LeaseState {
+    fn from(state: i32) -> Self {
+        match State::try_from(state) {
+            Ok(State::Active) => LeaseState::Active,
+            Ok(State::InsufficientFunds) => LeaseState::InsufficientFunds,
+            Ok(State::Closed) => LeaseState::Closed,
+            Ok(State::Invalid) | Err(_) => LeaseState::Invalid,
+        }
+    }
 }
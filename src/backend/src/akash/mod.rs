@@ -4,6 +4,7 @@ pub mod bank;
 pub mod bids;
 pub mod certificate;
 pub mod deployment;
+pub mod events;
 pub mod lease;
 pub mod proto;
 pub mod provider;
@@ -0,0 +1,14 @@
+pub mod query;
+
+/// Provider stores owner, auditor and the attributes signed by the auditor.
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/audit/v1beta3/audit.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Provider {
+    #[prost(string, tag = "1")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub auditor: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "4")]
+    pub attributes: ::prost::alloc::vec::Vec<super::base::attribute::Attribute>,
+}
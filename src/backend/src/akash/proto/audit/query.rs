@@ -0,0 +1,23 @@
+/// QueryProviderAttributesRequest is request type for the Query/ProviderAttributes RPC method.
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/audit/v1beta3/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryProviderAttributesRequest {
+    #[prost(string, tag = "1")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(message, tag = "2")]
+    pub pagination:
+        ::core::option::Option<cosmrs::proto::cosmos::base::query::v1beta1::PageRequest>,
+}
+
+/// QueryProvidersResponse is response type for the Query/ProviderAttributes RPC method.
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/audit/v1beta3/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryProvidersResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub providers: ::prost::alloc::vec::Vec<super::Provider>,
+    #[prost(message, tag = "2")]
+    pub pagination:
+        ::core::option::Option<cosmrs::proto::cosmos::base::query::v1beta1::PageResponse>,
+}
@@ -0,0 +1,42 @@
+use super::groupspec::GroupSpec;
+
+/// GroupID stores owner, deployment sequence number and group sequence number
+///
+/// from https://github.com/akash-network/akash-api/blob/8b3ecebafedd45c27653f34cfe8917cbdcc7c970/proto/node/akash/deployment/v1beta3/groupid.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupID {
+    #[prost(string, tag = "1")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub dseq: u64,
+    #[prost(uint32, tag = "3")]
+    pub gseq: u32,
+}
+
+/// Group stores group id, state and specifications of group
+///
+/// from https://github.com/akash-network/akash-api/blob/8b3ecebafedd45c27653f34cfe8917cbdcc7c970/proto/node/akash/deployment/v1beta3/group.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Group {
+    #[prost(message, tag = "1")]
+    pub group_id: ::core::option::Option<GroupID>,
+    #[prost(enumeration = "GroupState", tag = "2")]
+    pub state: i32,
+    #[prost(message, tag = "3")]
+    pub group_spec: ::core::option::Option<GroupSpec>,
+    #[prost(int64, tag = "4")]
+    pub created_at: i64,
+}
+
+/// State is an enum which refers to state of group
+///
+/// from https://github.com/akash-network/akash-api/blob/8b3ecebafedd45c27653f34cfe8917cbdcc7c970/proto/node/akash/deployment/v1beta3/group.proto
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum GroupState {
+    Invalid = 0,
+    Open = 1,
+    Paused = 2,
+    InsufficientFunds = 3,
+    Closed = 4,
+}
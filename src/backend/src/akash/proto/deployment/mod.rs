@@ -1,5 +1,7 @@
 pub mod deploymentmsg;
+pub mod group;
 pub mod groupspec;
+pub mod query;
 pub mod resourceunit;
 
 /// MsgCreateDeployment defines an SDK message for creating deployment.
@@ -12,3 +14,29 @@ pub struct DeploymentID {
     #[prost(uint64, tag = "2")]
     pub dseq: u64,
 }
+
+/// Deployment stores deploymentID, state and version details
+///
+/// from https://github.com/akash-network/akash-api/blob/8b3ecebafedd45c27653f34cfe8917cbdcc7c970/proto/node/akash/deployment/v1beta3/deployment.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Deployment {
+    #[prost(message, tag = "1")]
+    pub deployment_id: ::core::option::Option<DeploymentID>,
+    #[prost(enumeration = "DeploymentState", tag = "2")]
+    pub state: i32,
+    #[prost(bytes, tag = "3")]
+    pub version: ::prost::alloc::vec::Vec<u8>,
+    #[prost(int64, tag = "4")]
+    pub created_at: i64,
+}
+
+/// State is an enum which refers to state of deployment
+///
+/// from https://github.com/akash-network/akash-api/blob/8b3ecebafedd45c27653f34cfe8917cbdcc7c970/proto/node/akash/deployment/v1beta3/deployment.proto
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum DeploymentState {
+    Invalid = 0,
+    Active = 1,
+    Closed = 2,
+}
@@ -0,0 +1,23 @@
+use super::{group::Group, Deployment, DeploymentID};
+
+/// QueryDeploymentRequest is request type for the Query/Deployment RPC method
+///
+/// from https://github.com/akash-network/akash-api/blob/8b3ecebafedd45c27653f34cfe8917cbdcc7c970/proto/node/akash/deployment/v1beta3/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDeploymentRequest {
+    #[prost(message, tag = "1")]
+    pub id: ::core::option::Option<DeploymentID>,
+}
+
+/// QueryDeploymentResponse is response type for the Query/Deployment RPC method
+///
+/// from https://github.com/akash-network/akash-api/blob/8b3ecebafedd45c27653f34cfe8917cbdcc7c970/proto/node/akash/deployment/v1beta3/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDeploymentResponse {
+    #[prost(message, tag = "1")]
+    pub deployment: ::core::option::Option<Deployment>,
+    #[prost(message, repeated, tag = "2")]
+    pub groups: ::prost::alloc::vec::Vec<Group>,
+    #[prost(message, tag = "3")]
+    pub escrow_account: ::core::option::Option<super::super::escrow::types::Account>,
+}
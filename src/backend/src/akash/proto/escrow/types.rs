@@ -41,3 +41,34 @@ pub enum State {
     AccountClosed = 2,
     AccountOverdrawn = 3,
 }
+
+/// Payment stores state for a payment
+///
+/// https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/escrow/v1beta3/types.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FractionalPayment {
+    #[prost(message, tag = "1")]
+    pub account_id: ::core::option::Option<AccountId>,
+    #[prost(string, tag = "2")]
+    pub payment_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(enumeration = "PaymentState", tag = "4")]
+    pub state: i32,
+    #[prost(message, tag = "5")]
+    pub rate: ::core::option::Option<cosmrs::proto::cosmos::base::v1beta1::DecCoin>,
+    #[prost(message, tag = "6")]
+    pub balance: ::core::option::Option<cosmrs::proto::cosmos::base::v1beta1::DecCoin>,
+    #[prost(message, tag = "7")]
+    pub withdrawn: ::core::option::Option<cosmrs::proto::cosmos::base::v1beta1::Coin>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[allow(clippy::enum_variant_names)]
+pub enum PaymentState {
+    PaymentStateInvalid = 0,
+    PaymentOpen = 1,
+    PaymentClosed = 2,
+    PaymentOverdrawn = 3,
+}
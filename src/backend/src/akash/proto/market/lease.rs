@@ -15,3 +15,80 @@ impl Name for MsgCreateLease {
     const NAME: &'static str = "MsgCreateLease";
     const PACKAGE: &'static str = "akash.market.v1beta4";
 }
+
+/// LeaseID stores bid details of lease
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/lease.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LeaseId {
+    #[prost(string, tag = "1")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub dseq: u64,
+    #[prost(uint32, tag = "3")]
+    pub gseq: u32,
+    #[prost(uint32, tag = "4")]
+    pub oseq: u32,
+    #[prost(string, tag = "5")]
+    pub provider: ::prost::alloc::string::String,
+}
+
+impl From<&BidId> for LeaseId {
+    fn from(bid_id: &BidId) -> Self {
+        Self {
+            owner: bid_id.owner.clone(),
+            dseq: bid_id.dseq,
+            gseq: bid_id.gseq,
+            oseq: bid_id.oseq,
+            provider: bid_id.provider.clone(),
+        }
+    }
+}
+
+/// Lease stores LeaseID, state of lease and price
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/lease.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Lease {
+    #[prost(message, tag = "1")]
+    pub lease_id: ::core::option::Option<LeaseId>,
+    #[prost(enumeration = "State", tag = "2")]
+    pub state: i32,
+    #[prost(message, tag = "3")]
+    pub price: ::core::option::Option<cosmrs::proto::cosmos::base::v1beta1::DecCoin>,
+    #[prost(int64, tag = "4")]
+    pub created_at: i64,
+    #[prost(int64, tag = "5")]
+    pub closed_on: i64,
+}
+
+/// LeaseFilters defines flags for lease list filter
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/lease.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LeaseFilters {
+    #[prost(string, tag = "1")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub dseq: u64,
+    #[prost(uint32, tag = "3")]
+    pub gseq: u32,
+    #[prost(uint32, tag = "4")]
+    pub oseq: u32,
+    #[prost(string, tag = "5")]
+    pub provider: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub state: ::prost::alloc::string::String,
+}
+
+/// State is an enum which refers to state of lease
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/lease.proto
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum State {
+    Invalid = 0,
+    Active = 1,
+    InsufficientFunds = 2,
+    Closed = 3,
+}
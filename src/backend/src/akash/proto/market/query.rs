@@ -19,7 +19,16 @@ pub struct QueryBidsResponse {
     pub bids: ::prost::alloc::vec::Vec<QueryBidResponse>,
     #[prost(message, tag = "2")]
     pub pagination:
-        ::core::option::Option<cosmrs::proto::cosmos::base::query::v1beta1::PageRequest>,
+        ::core::option::Option<cosmrs::proto::cosmos::base::query::v1beta1::PageResponse>,
+}
+
+/// QueryBidRequest is request type for the Query/Bid RPC method
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryBidRequest {
+    #[prost(message, tag = "1")]
+    pub id: ::core::option::Option<super::bid::BidId>,
 }
 
 /// QueryBidResponse is response type for the Query/Bid RPC method
@@ -32,3 +41,47 @@ pub struct QueryBidResponse {
     #[prost(message, tag = "2")]
     pub escrow_account: ::core::option::Option<super::super::escrow::types::Account>,
 }
+
+/// QueryLeaseRequest is request type for the Query/Lease RPC method
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryLeaseRequest {
+    #[prost(message, tag = "1")]
+    pub id: ::core::option::Option<super::lease::LeaseId>,
+}
+
+/// QueryLeasesRequest is request type for the Query/Leases RPC method
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryLeasesRequest {
+    #[prost(message, tag = "1")]
+    pub filters: ::core::option::Option<super::lease::LeaseFilters>,
+    #[prost(message, tag = "2")]
+    pub pagination:
+        ::core::option::Option<cosmrs::proto::cosmos::base::query::v1beta1::PageRequest>,
+}
+
+/// QueryLeasesResponse is response type for the Query/Leases RPC method
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryLeasesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub leases: ::prost::alloc::vec::Vec<QueryLeaseResponse>,
+    #[prost(message, tag = "2")]
+    pub pagination:
+        ::core::option::Option<cosmrs::proto::cosmos::base::query::v1beta1::PageResponse>,
+}
+
+/// QueryLeaseResponse is response type for the Query/Lease RPC method
+///
+/// from https://github.com/akash-network/akash-api/blob/40e1584bc52f8753296e07a562265a034bf35bef/proto/node/akash/market/v1beta4/query.proto
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryLeaseResponse {
+    #[prost(message, tag = "1")]
+    pub lease: ::core::option::Option<super::lease::Lease>,
+    #[prost(message, tag = "2")]
+    pub escrow_payment: ::core::option::Option<super::super::escrow::types::FractionalPayment>,
+}
@@ -1,3 +1,4 @@
+pub mod audit;
 pub mod base;
 pub mod cert;
 pub mod deployment;
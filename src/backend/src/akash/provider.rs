@@ -1,10 +1,21 @@
+use cosmrs::proto::cosmos::base::query::v1beta1::PageRequest;
 use prost::Message;
 
-use super::proto::provider::{
-    query::{QueryProviderRequest, QueryProviderResponse},
-    Provider,
+use super::proto::{
+    audit::{
+        query::{QueryProviderAttributesRequest, QueryProvidersResponse},
+        Provider as AuditedProvider,
+    },
+    provider::{
+        query::{QueryProviderRequest, QueryProviderResponse},
+        Provider,
+    },
 };
 
+const PROVIDER_AUDITS_PAGE_LIMIT: u64 = 10;
+/// upper bound on the pages fetched, to limit the number of HTTPS outcalls
+const MAX_PROVIDER_AUDITS_PAGES: usize = 5;
+
 pub async fn fetch_provider(rpc_url: String, provider_address: String) -> Result<Provider, String> {
     let query = QueryProviderRequest {
         owner: provider_address,
@@ -24,3 +35,55 @@ pub async fn fetch_provider(rpc_url: String, provider_address: String) -> Result
 
     Ok(res.provider.unwrap())
 }
+
+/// Fetches the attributes of the provider signed by each auditor, following the pagination.
+pub async fn fetch_provider_audits(
+    rpc_url: String,
+    provider_address: String,
+) -> Result<Vec<AuditedProvider>, String> {
+    let mut audits = vec![];
+    let mut next_key = vec![];
+
+    for _ in 0..MAX_PROVIDER_AUDITS_PAGES {
+        let query = QueryProviderAttributesRequest {
+            owner: provider_address.clone(),
+            pagination: Some(PageRequest {
+                key: next_key,
+                limit: PROVIDER_AUDITS_PAGE_LIMIT,
+                offset: 0,
+                count_total: false,
+                reverse: false,
+            }),
+        };
+
+        let abci_res = ic_tendermint_rpc::abci_query(
+            rpc_url.clone(),
+            Some(String::from(
+                "/akash.audit.v1beta3.Query/ProviderAttributes",
+            )),
+            query.encode_to_vec(),
+            None,
+            false,
+        )
+        .await?;
+
+        if abci_res.response.code.is_err() {
+            return Err(format!(
+                "could not fetch provider audits: {}",
+                abci_res.response.log
+            ));
+        }
+
+        let res = QueryProvidersResponse::decode(abci_res.response.value.as_slice())
+            .map_err(|e| e.to_string())?;
+
+        audits.extend(res.providers);
+
+        next_key = res.pagination.map(|p| p.next_key).unwrap_or_default();
+        if next_key.is_empty() {
+            break;
+        }
+    }
+
+    Ok(audits)
+}
@@ -1,4 +1,5 @@
-mod sizes;
+mod resources;
+pub mod sizes;
 
 use std::{cmp::Ordering, collections::HashMap};
 
@@ -7,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use utils::sha256;
 
-use crate::api::DeploymentParams;
+use crate::api::{DeploymentParams, ResourceTiers};
 
 use super::proto::{
     base::{
@@ -25,6 +26,7 @@ use super::proto::{
     deployment::{groupspec::GroupSpec, resourceunit::ResourceUnit as ProtobufResourceUnit},
 };
 
+pub use resources::SdlResourceRequest;
 use sizes::{convert_cpu_resource_string, convert_resource_string};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -517,13 +519,68 @@ impl SdlV3 {
         Ok(parsed_sdl)
     }
 
+    /// Checks that the deployment can be updated from this SDL to the updated one.
+    ///
+    /// Akash does not allow changing the groups of an existing deployment,
+    /// so the updated SDL must request the same resources with the same placement requirements.
+    pub fn check_update_compatibility(&self, updated: &SdlV3) -> Result<(), String> {
+        let groups = self.groups();
+        let updated_groups = updated.groups();
+
+        if groups.len() != updated_groups.len() {
+            return Err(format!(
+                "the number of groups cannot change: {} groups, {} in the updated SDL",
+                groups.len(),
+                updated_groups.len()
+            ));
+        }
+
+        for (group, updated_group) in groups.iter().zip(updated_groups.iter()) {
+            if group.name != updated_group.name {
+                return Err(format!(
+                    "group {} is not in the updated SDL, found group {}",
+                    group.name, updated_group.name
+                ));
+            }
+
+            if group.requirements != updated_group.requirements {
+                return Err(format!(
+                    "the placement requirements of group {} cannot change",
+                    group.name
+                ));
+            }
+
+            // the price is not compared as the leases have already been created
+            let same_resources = group.resources.len() == updated_group.resources.len()
+                && group
+                    .resources
+                    .iter()
+                    .zip(updated_group.resources.iter())
+                    .all(|(resource, updated_resource)| {
+                        resource.resource == updated_resource.resource
+                            && resource.count == updated_resource.count
+                    });
+            if !same_resources {
+                return Err(format!(
+                    "the resources of group {} cannot change",
+                    group.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn to_yaml(&self) -> Result<String, String> {
         serde_yaml::to_string(self).map_err(|e| e.to_string())
     }
 
-    pub fn try_from_deployment_params(sdl_params: DeploymentParams) -> Result<SdlV3, String> {
+    pub fn try_from_deployment_params(
+        sdl_params: DeploymentParams,
+        resource_tiers: &ResourceTiers,
+    ) -> Result<SdlV3, String> {
         let service_name = sdl_params.name;
-        Ok(SdlV3 {
+        let sdl = SdlV3 {
             version: "3.0".to_string(),
             services: {
                 let mut services = HashMap::new();
@@ -607,16 +664,16 @@ impl SdlV3 {
                             ProfileComputeV3 {
                                 resources: ComputeResourcesV3 {
                                     cpu: ResourceCpuV2 {
-                                        units: sdl_params.cpu.to_unit(),
+                                        units: sdl_params.cpu.to_unit(&resource_tiers.cpu),
                                         attributes: None,
                                     },
                                     memory: ResourceMemoryV2 {
-                                        size: sdl_params.memory.to_size(),
+                                        size: sdl_params.memory.to_size(&resource_tiers.memory),
                                         attributes: None,
                                     },
                                     storage: vec![ResourceStorageV2 {
                                         name: None,
-                                        size: sdl_params.storage.to_size(),
+                                        size: sdl_params.storage.to_size(&resource_tiers.storage),
                                         attributes: None,
                                     }],
                                     gpu: Some(ResourceGpuV3 {
@@ -670,11 +727,17 @@ impl SdlV3 {
                 deployment
             },
             endpoints: None,
-        })
+        };
+
+        // custom sizes are provided by the user and must be validated
+        sdl.validate()?;
+
+        Ok(sdl)
     }
 
     fn validate(&self) -> Result<(), String> {
         for (name, profile) in self.profiles.compute.iter() {
+            validate_resources(name, &profile.resources)?;
             validate_gpu(name, profile.resources.gpu.clone())?;
         }
 
@@ -1220,6 +1283,34 @@ fn service_resource_gpu(resource: &Option<ResourceGpuV3>) -> GenericResource {
     }
 }
 
+fn validate_resources(name: &String, resources: &ComputeResourcesV3) -> Result<(), String> {
+    if convert_cpu_resource_string(&resources.cpu.units)? == 0 {
+        return Err(format!(
+            "CPU units must be greater than 0 for profile {}",
+            name
+        ));
+    }
+
+    if convert_resource_string(&resources.memory.size)? <= 0.0 {
+        return Err(format!(
+            "Memory size must be greater than 0 for profile {}",
+            name
+        ));
+    }
+
+    for storage in resources.storage.iter() {
+        if convert_resource_string(&storage.size)? <= 0.0 {
+            return Err(format!(
+                "Storage {} size must be greater than 0 for profile {}",
+                storage.name(),
+                name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 fn validate_gpu(name: &String, gpu: Option<ResourceGpuV3>) -> Result<(), String> {
     let gpu = match gpu {
         Some(g) => g,
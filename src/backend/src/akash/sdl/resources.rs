@@ -0,0 +1,65 @@
+use super::{
+    sizes::{convert_cpu_resource_string, convert_resource_string},
+    SdlV3,
+};
+
+/// Resources requested by a service in a placement, together with the maximum price
+/// the deployment is willing to pay for them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SdlResourceRequest {
+    pub cpu_millis: u32,
+    pub memory_bytes: f64,
+    pub storage_bytes: f64,
+    pub gpu_units: u32,
+    /// number of replicas of the service
+    pub count: u32,
+    /// max price per block of a single replica, from the `pricing` of the placement profile
+    pub max_price_uakt: u32,
+}
+
+impl SdlV3 {
+    /// Lists the resources requested by each service in each placement.
+    pub fn resource_requests(&self) -> Result<Vec<SdlResourceRequest>, String> {
+        let mut requests = vec![];
+
+        for (service_name, placements) in self.deployment.iter() {
+            for (placement_name, svc_depl) in placements.iter() {
+                let compute = self.profiles.compute.get(&svc_depl.profile).ok_or(format!(
+                    "compute profile {} of service {} not found",
+                    svc_depl.profile, service_name
+                ))?;
+                let pricing = self
+                    .profiles
+                    .placement
+                    .get(placement_name)
+                    .and_then(|placement| placement.pricing.get(&svc_depl.profile))
+                    .ok_or(format!(
+                        "pricing of profile {} in placement {} not found",
+                        svc_depl.profile, placement_name
+                    ))?;
+
+                let resources = &compute.resources;
+                let storage_bytes = resources
+                    .storage
+                    .iter()
+                    .map(|storage| convert_resource_string(&storage.size))
+                    .sum::<Result<f64, String>>()?;
+                let gpu_units = match &resources.gpu {
+                    Some(gpu) => gpu.units().parse::<u32>().map_err(|e| e.to_string())?,
+                    None => 0,
+                };
+
+                requests.push(SdlResourceRequest {
+                    cpu_millis: convert_cpu_resource_string(&resources.cpu.units)?,
+                    memory_bytes: convert_resource_string(&resources.memory.size)?,
+                    storage_bytes,
+                    gpu_units,
+                    count: svc_depl.count,
+                    max_price_uakt: pricing.amount,
+                });
+            }
+        }
+
+        Ok(requests)
+    }
+}
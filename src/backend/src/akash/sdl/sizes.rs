@@ -5,7 +5,12 @@ const PREFIXES: [&str; 6] = ["k", "m", "g", "t", "p", "e"];
 fn parse_size_string(size: &str) -> Result<(f64, String, String), String> {
     let regex = regex::Regex::new(r"^([\d.]+)([a-zA-Z])([a-zA-Z]*)$").unwrap();
     if let Some(captures) = regex.captures(size) {
-        let value = captures.get(1).unwrap().as_str().parse::<f64>().unwrap();
+        let value = captures
+            .get(1)
+            .unwrap()
+            .as_str()
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid size string: {}", size))?;
         let unit1 = captures.get(2).unwrap().as_str().to_lowercase();
         let unit2 = captures.get(3).unwrap().as_str().to_lowercase();
         Ok((value, unit1, unit2))
@@ -35,7 +40,12 @@ pub fn convert_resource_string(resource_str: &str) -> Result<f64, String> {
 fn parse_cpu_resource_string(size: &str) -> Result<(f64, String), String> {
     let regex = regex::Regex::new(r"^([\d.]+)([a-zA-Z]*)$").unwrap();
     if let Some(captures) = regex.captures(size) {
-        let value = captures.get(1).unwrap().as_str().parse::<f64>().unwrap();
+        let value = captures
+            .get(1)
+            .unwrap()
+            .as_str()
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid size string: {}", size))?;
         let unit = captures.get(2).unwrap().as_str().to_lowercase();
         Ok((value, unit))
     } else {
@@ -1,15 +1,63 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use cosmrs::{
-    crypto::PublicKey,
-    proto::cosmos::tx::v1beta1::TxRaw,
+    crypto::{
+        secp256k1::{Signature, VerifyingKey},
+        PublicKey,
+    },
+    proto::cosmos::tx::v1beta1::{SimulateRequest, SimulateResponse, TxRaw},
+    tendermint::block::Height,
     tendermint::chain::Id,
     tx::{self, Fee, SignDoc, SignerInfo},
-    Any, Tx,
+    Any, Coin, Denom, Tx,
 };
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use prost::Message;
+use serde::Serialize;
 use utils::sha256;
 
-use crate::helpers::{sign, EcdsaKeyIds};
+use crate::{
+    api::{log_info, FeeConfig},
+    helpers::{sign, EcdsaKeyIds},
+};
+
+/// Message to include in a transaction, together with the maximum gas it may use.
+pub struct TxMessage {
+    pub msg: Any,
+    pub max_gas: u64,
+}
+
+/// Memo of the transactions, serialized as JSON,
+/// so that the transactions on chain can be traced back to the canister records.
+#[derive(Serialize)]
+pub struct TxMemo {
+    pub canister_id: String,
+    /// the deployment for which the transaction has been made, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment_id: Option<String>,
+}
+
+impl TxMemo {
+    pub fn new(deployment_id: Option<String>) -> Self {
+        Self {
+            canister_id: ic_cdk::id().to_text(),
+            deployment_id,
+        }
+    }
+}
+
+/// Chain, signing key and fee policy used to create the transactions.
+pub struct TxConfig<'a> {
+    pub rpc_url: String,
+    pub chain_id: &'a str,
+    pub ecdsa_key: &'a EcdsaKeyIds,
+    /// derivation path of the key of the signing account
+    pub derivation_path: Vec<Vec<u8>>,
+    pub fees: &'a FeeConfig,
+    /// the transaction is rejected if it is not included in a block up to this height, 0 means no timeout
+    pub timeout_height: u64,
+    pub memo: TxMemo,
+}
 
 /// from https://docs.rs/cosmrs/latest/cosmrs/tx/index.html#usage
 ///
@@ -77,21 +125,35 @@ use crate::helpers::{sign, EcdsaKeyIds};
 ///
 /// more config params from: https://github.com/akash-network/net/blob/main/sandbox/meta.json
 /// see also: https://docs.akash.network/guides/sandbox/detailed-steps/part-4.-configure-your-network
+///
+/// The messages are executed in order and atomically: if one fails, none of them is applied.
+///
+/// The gas limit and the fee are computed from the gas used when simulating the transaction,
+/// see [FeeConfig]. The transaction is rejected without being broadcast if its gas limit
+/// exceeds the sum of the maximum gas of the messages.
 pub async fn create_tx(
     sender_public_key: &PublicKey,
-    msg: Any,
-    fee: Fee,
+    msgs: Vec<TxMessage>,
     sequence_number: u64,
     account_number: u64,
-    ecdsa_key: &EcdsaKeyIds,
-    chain_id: &str,
+    tx_config: &TxConfig<'_>,
 ) -> Result<Vec<u8>, String> {
-    let chain_id = Id::from_str(chain_id).map_err(|e| e.to_string())?;
-    let timeout_height = 0u16;
-    let memo = format!("canister id: {}", ic_cdk::id().to_text());
+    let chain_id = Id::from_str(tx_config.chain_id).map_err(|e| e.to_string())?;
+    let timeout_height = Height::try_from(tx_config.timeout_height).map_err(|e| e.to_string())?;
+    let memo = serde_json::to_string(&tx_config.memo).map_err(|e| e.to_string())?;
 
-    // Create transaction body from the MsgSend, memo, and timeout height.
-    let tx_body = tx::Body::new(vec![msg], memo, timeout_height);
+    if msgs.is_empty() {
+        return Err(String::from("a transaction must have at least one message"));
+    }
+    let max_gas = msgs.iter().map(|msg| msg.max_gas).sum();
+    let type_urls = msgs
+        .iter()
+        .map(|msg| msg.msg.type_url.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // Create transaction body from the messages, memo, and timeout height.
+    let tx_body = tx::Body::new(msgs.into_iter().map(|msg| msg.msg), memo, timeout_height);
 
     // print(format!("tx_body: {:?}", tx_body));
 
@@ -99,6 +161,35 @@ pub async fn create_tx(
     // This uses a standard "direct" signature from a single signer.
     let signer_info = SignerInfo::single_direct(Some(*sender_public_key), sequence_number);
 
+    let simulated_gas =
+        simulate_tx(tx_config.rpc_url.clone(), &tx_body, signer_info.clone()).await?;
+    let gas_limit = tx_config.fees.gas_limit(simulated_gas, max_gas)?;
+    let fee_uakt = tx_config.fees.fee_uakt(gas_limit);
+
+    log_info!(
+        format!(
+            "[{}]: simulated gas {} x {} = gas limit {} (max {}), fee {} uakt at {} uakt/gas, timeout height {}, memo {}",
+            type_urls,
+            simulated_gas,
+            tx_config.fees.gas_multiplier,
+            gas_limit,
+            max_gas,
+            fee_uakt,
+            tx_config.fees.gas_price_uakt,
+            tx_body.timeout_height,
+            tx_body.memo,
+        ),
+        "create_tx"
+    );
+
+    let fee = Fee::from_amount_and_gas(
+        Coin {
+            amount: fee_uakt.into(),
+            denom: Denom::from_str("uakt").unwrap(),
+        },
+        gas_limit,
+    );
+
     // Compute auth info from signer info by associating a fee.
     let auth_info = signer_info.auth_info(fee);
 
@@ -111,7 +202,14 @@ pub async fn create_tx(
         SignDoc::new(&tx_body, &auth_info, &chain_id, account_number).map_err(|e| e.to_string())?;
 
     // Sign the "sign doc" with the sender's private key, producing a signed raw transaction.
-    let tx_signed = sign_tx(sign_doc, ecdsa_key).await?;
+    let tx_signed = sign_tx(
+        sign_doc,
+        sender_public_key,
+        tx_config.ecdsa_key,
+        tx_config.derivation_path.clone(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
 
     // Serialize the raw transaction as bytes (i.e. `Vec<u8>`).
     let tx_bytes = tx_signed.to_bytes().map_err(|e| e.to_string())?;
@@ -122,20 +220,117 @@ pub async fn create_tx(
 
     // Parse the serialized bytes from above into a `cosmrs::Tx`
     let tx_parsed = Tx::from_bytes(&tx_bytes).map_err(|e| e.to_string())?;
-    assert_eq!(tx_parsed.body, tx_body);
-    assert_eq!(tx_parsed.auth_info, auth_info);
+    if tx_parsed.body != tx_body || tx_parsed.auth_info != auth_info {
+        return Err(SignTxError::RoundTripMismatch.to_string());
+    }
 
     // print(format!("tx_parsed: {:?}", tx_parsed));
 
     Ok(tx_bytes)
 }
 
+/// Simulates the transaction on the node and returns the gas it used.
+///
+/// The signature is not verified in simulation mode, so an empty one is sent
+/// instead of signing the transaction with the canister's key.
+async fn simulate_tx(
+    rpc_url: String,
+    tx_body: &tx::Body,
+    signer_info: SignerInfo,
+) -> Result<u64, String> {
+    let auth_info = signer_info.auth_info(Fee {
+        amount: vec![],
+        gas_limit: 0,
+        payer: None,
+        granter: None,
+    });
+
+    let tx_raw = TxRaw {
+        body_bytes: tx_body.clone().into_bytes().map_err(|e| e.to_string())?,
+        auth_info_bytes: auth_info.into_bytes().map_err(|e| e.to_string())?,
+        signatures: vec![vec![]],
+    };
+
+    #[allow(deprecated)]
+    let query = SimulateRequest {
+        tx: None,
+        tx_bytes: tx_raw.encode_to_vec(),
+    };
+
+    let abci_res = ic_tendermint_rpc::abci_query(
+        rpc_url,
+        Some(String::from("/cosmos.tx.v1beta1.Service/Simulate")),
+        query.encode_to_vec(),
+        None,
+        false,
+    )
+    .await?;
+
+    if abci_res.response.code.is_err() {
+        return Err(format!(
+            "transaction simulation failed: {}",
+            abci_res.response.log
+        ));
+    }
+
+    let res =
+        SimulateResponse::decode(abci_res.response.value.as_slice()).map_err(|e| e.to_string())?;
+
+    res.gas_info
+        .map(|gas_info| gas_info.gas_used)
+        .ok_or(String::from("simulation returned no gas info"))
+}
+
+/// Why a transaction could not be signed.
+#[derive(Debug, PartialEq)]
+pub enum SignTxError {
+    /// the sign doc could not be encoded
+    Encoding(String),
+    /// the threshold ECDSA signing call failed
+    Signing(String),
+    /// the signature is not a 64 bytes (r, s) secp256k1 signature
+    MalformedSignature(String),
+    /// the signature does not verify against the sign doc hash and the public key
+    InvalidSignature(String),
+    /// the signed transaction does not decode to the body and auth info that were signed
+    RoundTripMismatch,
+}
+
+impl fmt::Display for SignTxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignTxError::Encoding(e) => write!(f, "could not encode sign doc: {}", e),
+            SignTxError::Signing(e) => write!(f, "could not sign transaction: {}", e),
+            SignTxError::MalformedSignature(e) => write!(f, "malformed signature: {}", e),
+            SignTxError::InvalidSignature(e) => write!(f, "invalid signature: {}", e),
+            SignTxError::RoundTripMismatch => write!(
+                f,
+                "signed transaction does not match the transaction that was signed"
+            ),
+        }
+    }
+}
+
 /// adapted form https://docs.rs/cosmrs/latest/cosmrs/tx/struct.SignDoc.html#method.sign
-async fn sign_tx(sign_doc: SignDoc, ecdsa_key: &EcdsaKeyIds) -> Result<tx::Raw, String> {
-    let sign_doc_bytes = sign_doc.clone().into_bytes().map_err(|e| e.to_string())?;
+///
+/// The signature is verified with the public key before being used,
+/// so that a wrong key or signature is detected before broadcasting the transaction.
+async fn sign_tx(
+    sign_doc: SignDoc,
+    public_key: &PublicKey,
+    ecdsa_key: &EcdsaKeyIds,
+    derivation_path: Vec<Vec<u8>>,
+) -> Result<tx::Raw, SignTxError> {
+    let sign_doc_bytes = sign_doc
+        .clone()
+        .into_bytes()
+        .map_err(|e| SignTxError::Encoding(e.to_string()))?;
     let hash = sha256(&sign_doc_bytes);
 
-    let signature = sign(hash.to_vec(), ecdsa_key).await.unwrap();
+    let signature = sign(hash.to_vec(), ecdsa_key, derivation_path)
+        .await
+        .map_err(SignTxError::Signing)?;
+    let signature = verified_signature(public_key, &hash, &signature)?;
 
     Ok(TxRaw {
         body_bytes: sign_doc.body_bytes,
@@ -144,3 +339,66 @@ async fn sign_tx(sign_doc: SignDoc, ecdsa_key: &EcdsaKeyIds) -> Result<tx::Raw,
     }
     .into())
 }
+
+/// Normalizes the signature to the low-S form required by cosmos-sdk,
+/// which rejects the malleable high-S signatures, and verifies it against the message hash.
+fn verified_signature(
+    public_key: &PublicKey,
+    message_hash: &[u8],
+    signature: &[u8],
+) -> Result<Vec<u8>, SignTxError> {
+    let signature = Signature::from_slice(signature)
+        .map_err(|e| SignTxError::MalformedSignature(e.to_string()))?;
+    let signature = signature.normalize_s().unwrap_or(signature);
+
+    VerifyingKey::from_sec1_bytes(&public_key.to_bytes())
+        .and_then(|key| key.verify_prehash(message_hash, &signature))
+        .map_err(|e| SignTxError::InvalidSignature(e.to_string()))?;
+
+    Ok(signature.to_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{signature::hazmat::PrehashSigner, SigningKey};
+
+    use super::*;
+
+    fn sign_prehash(message_hash: &[u8]) -> (PublicKey, Signature) {
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let signature: Signature = signing_key.sign_prehash(message_hash).unwrap();
+
+        (PublicKey::from(*signing_key.verifying_key()), signature)
+    }
+
+    #[test]
+    fn test_normalize_high_s_signature() {
+        let message_hash = sha256(b"sign doc");
+        let (public_key, signature) = sign_prehash(&message_hash);
+        let (r, s) = signature.split_scalars();
+        let high_s = Signature::from_scalars(r, -*s).unwrap();
+        let low_s = signature.normalize_s().unwrap_or(signature);
+
+        for signature in [low_s, high_s] {
+            assert_eq!(
+                verified_signature(&public_key, &message_hash, &signature.to_bytes()),
+                Ok(low_s.to_bytes().to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn test_reject_invalid_signature() {
+        let message_hash = sha256(b"sign doc");
+        let (public_key, signature) = sign_prehash(&message_hash);
+
+        assert!(matches!(
+            verified_signature(&public_key, &sha256(b"other"), &signature.to_bytes()),
+            Err(SignTxError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            verified_signature(&public_key, &message_hash, &[0; 10]),
+            Err(SignTxError::MalformedSignature(_))
+        ));
+    }
+}
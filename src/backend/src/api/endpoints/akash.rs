@@ -1,9 +1,11 @@
-use crate::api::{AkashService, ApiError, ApiResult};
-use ic_cdk::update;
+use std::time::Duration;
 
-#[update]
-async fn address() -> ApiResult<String> {
-    AkashEndpoints::default().address().await.into()
+use crate::api::{log_info, log_warn, AkashService, ApiError, ApiResult};
+use ic_cdk::{query, update};
+
+#[query]
+fn address() -> ApiResult<String> {
+    AkashEndpoints::default().address().into()
 }
 
 #[update]
@@ -22,11 +24,12 @@ struct AkashEndpoints {
 }
 
 impl AkashEndpoints {
-    async fn address(&self) -> Result<String, ApiError> {
+    fn address(&self) -> Result<String, ApiError> {
         self.akash_service
-            .address()
-            .await
-            .map_err(|e| ApiError::internal(&format!("failed to get address: {}", e)))
+            .cached_address()
+            .ok_or(ApiError::not_found(
+                "Address not available yet, retry later",
+            ))
     }
 
     async fn balance(&self) -> Result<u64, ApiError> {
@@ -40,6 +43,27 @@ impl AkashEndpoints {
         self.akash_service
             .check_tx(tx_hash_hex)
             .await
+            .map(|_| ())
             .map_err(|e| ApiError::internal(&format!("failed to check tx: {}", e)))
     }
 }
+
+/// Fetches the public key of the canister and caches it, so that the address can be served by a query.
+///
+/// The cache survives upgrades, in which case no call is made.
+pub fn init_canister_account_cache() {
+    ic_cdk_timers::set_timer(Duration::ZERO, || {
+        ic_cdk::spawn(async {
+            match AkashService::default().address().await {
+                Ok(address) => log_info!(
+                    format!("Canister account: {}", address),
+                    "init_canister_account_cache"
+                ),
+                Err(e) => log_warn!(
+                    format!("Could not fetch canister account: {}", e),
+                    "init_canister_account_cache"
+                ),
+            }
+        })
+    });
+}
@@ -0,0 +1,312 @@
+use std::time::Duration;
+
+use utils::get_time_nanos;
+
+use crate::{
+    api::{
+        log_error, log_info, log_warn, AkashService, AktLedgerEntryKind, ApiError, AutoTopUp,
+        Deployment, DeploymentId, DeploymentsService, PendingDeposit, TxError, UserId,
+        UsersService,
+    },
+    helpers::uakt_to_akt,
+};
+
+const AUTO_TOP_UP_JOB_INTERVAL_SECS: u64 = 600;
+
+/// Starts the job that tops up the escrow of the deployments with auto top-up enabled
+/// and settles the deposits that were not confirmed when they were made.
+pub fn init_auto_top_up_job() {
+    ic_cdk_timers::set_timer_interval(Duration::from_secs(AUTO_TOP_UP_JOB_INTERVAL_SECS), || {
+        ic_cdk::spawn(process_auto_top_ups())
+    });
+}
+
+async fn process_auto_top_ups() {
+    for (deployment_id, deployment) in DeploymentsService::default().get_deployments_to_top_up() {
+        if let Err(e) = process_auto_top_up(deployment_id, deployment).await {
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Failed to top up the escrow, will retry: {:?}",
+                    deployment_id, e
+                ),
+                "process_auto_top_ups"
+            );
+        }
+    }
+}
+
+async fn process_auto_top_up(
+    deployment_id: DeploymentId,
+    deployment: Deployment,
+) -> Result<(), ApiError> {
+    let akash_service = AkashService::default()
+        .with_timer_polling()
+        .with_account(deployment.akash_account());
+    let mut deployments_service = DeploymentsService::default();
+    let mut users_service = UsersService::default();
+    let user_id = deployment.user_id();
+
+    let mut top_up_pending = false;
+    for pending_deposit in deployment.pending_deposits() {
+        let is_auto_top_up = pending_deposit.is_auto_top_up;
+        let settled = settle_pending_deposit(
+            &akash_service,
+            &mut deployments_service,
+            &mut users_service,
+            deployment_id,
+            user_id,
+            pending_deposit,
+        )
+        .await?;
+
+        top_up_pending |= is_auto_top_up && !settled;
+    }
+    // the escrow is not topped up again until the previous top-up is settled
+    if top_up_pending {
+        return Ok(());
+    }
+
+    // the deployment may have been listed only to settle its deposits
+    let deployment = deployments_service.get_deployment(&deployment_id)?;
+    if !deployment.needs_auto_top_up() {
+        return Ok(());
+    }
+    let (Some(dseq), Some(auto_top_up)) = (deployment.get_akash_info(), deployment.auto_top_up())
+    else {
+        return Ok(());
+    };
+
+    let Some(lease_status) = akash_service
+        .lease_status(dseq)
+        .await
+        .map_err(|e| ApiError::internal(&format!("Error fetching lease status: {}", e)))?
+    else {
+        return Ok(());
+    };
+    // nothing to pay if there are no active leases
+    let Some(runway_blocks) = lease_status.estimated_runway_blocks else {
+        return Ok(());
+    };
+
+    let block_time_secs = deployments_service
+        .get_config()
+        .akash_config()
+        .pricing
+        .block_time_secs;
+    let min_runway_blocks = auto_top_up.settings.min_runway.blocks(block_time_secs);
+    if runway_blocks >= min_runway_blocks {
+        return Ok(());
+    }
+
+    let amount_uakt = auto_top_up.settings.amount_uakt;
+    let amount_akt = uakt_to_akt(amount_uakt);
+
+    if users_service.get_user_akt_balance(&user_id)? < amount_akt {
+        return stop_auto_top_up(
+            &mut deployments_service,
+            deployment_id,
+            auto_top_up,
+            format!(
+                "Not enough AKT balance to top up the escrow with {} AKT, {} blocks of runway left",
+                amount_akt, runway_blocks
+            ),
+        );
+    }
+
+    // the user is charged before depositing, so that the balance cannot change in between
+    if let Err(e) = charge_top_up(&mut users_service, user_id, deployment_id, amount_akt) {
+        return stop_auto_top_up(
+            &mut deployments_service,
+            deployment_id,
+            auto_top_up,
+            format!(
+                "Could not charge the top-up of {} AKT: {}",
+                amount_akt,
+                e.message()
+            ),
+        );
+    }
+
+    match akash_service
+        .deposit_deployment(deployment_id, dseq, amount_uakt)
+        .await
+    {
+        Ok(()) => {}
+        Err(TxError::Failed(e)) => {
+            refund_deposit(&mut users_service, user_id, deployment_id, amount_akt);
+
+            return Err(ApiError::internal(&format!(
+                "Error depositing to escrow: {}",
+                e
+            )));
+        }
+        // the deposit may still be applied, so the user is refunded only once it has definitely failed
+        Err(TxError::Unknown {
+            tx_hash,
+            timeout_height,
+            reason,
+        }) => {
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Top-up deposit {} not confirmed yet, will check it again: {}",
+                    deployment_id, tx_hash, reason
+                ),
+                "process_auto_top_up"
+            );
+
+            return deployments_service.add_pending_deposit(
+                deployment_id,
+                PendingDeposit {
+                    tx_hash,
+                    timeout_height,
+                    amount_uakt,
+                    is_auto_top_up: true,
+                },
+            );
+        }
+    }
+
+    record_top_up(&mut deployments_service, deployment_id)?;
+
+    log_info!(
+        format!(
+            "[Deployment {}]: Escrow topped up with {} uakt, {} blocks of runway were left",
+            deployment_id, amount_uakt, runway_blocks
+        ),
+        "process_auto_top_up"
+    );
+
+    Ok(())
+}
+
+/// Checks again a deposit that was not confirmed when it was made,
+/// refunding the user if it has definitely failed.
+///
+/// Returns whether the deposit has been settled.
+async fn settle_pending_deposit(
+    akash_service: &AkashService,
+    deployments_service: &mut DeploymentsService,
+    users_service: &mut UsersService,
+    deployment_id: DeploymentId,
+    user_id: UserId,
+    pending_deposit: PendingDeposit,
+) -> Result<bool, ApiError> {
+    match akash_service
+        .recheck_tx(&pending_deposit.tx_hash, pending_deposit.timeout_height)
+        .await
+    {
+        Ok(_) => {
+            deployments_service.remove_pending_deposit(deployment_id, &pending_deposit.tx_hash)?;
+            if pending_deposit.is_auto_top_up {
+                record_top_up(deployments_service, deployment_id)?;
+            }
+
+            log_info!(
+                format!(
+                    "[Deployment {}]: Escrow topped up with {} uakt in tx {}",
+                    deployment_id, pending_deposit.amount_uakt, pending_deposit.tx_hash
+                ),
+                "settle_pending_deposit"
+            );
+        }
+        Err(TxError::Failed(e)) => {
+            deployments_service.remove_pending_deposit(deployment_id, &pending_deposit.tx_hash)?;
+            refund_deposit(
+                users_service,
+                user_id,
+                deployment_id,
+                uakt_to_akt(pending_deposit.amount_uakt),
+            );
+
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Deposit {} failed: {}",
+                    deployment_id, pending_deposit.tx_hash, e
+                ),
+                "settle_pending_deposit"
+            );
+        }
+        // still pending, it is checked again at the next run
+        Err(TxError::Unknown { .. }) => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Records the time of the last top-up on the current settings,
+/// which the user may have changed while the deposit was being confirmed.
+fn record_top_up(
+    deployments_service: &mut DeploymentsService,
+    deployment_id: DeploymentId,
+) -> Result<(), ApiError> {
+    let Some(mut auto_top_up) = deployments_service
+        .get_deployment(&deployment_id)?
+        .auto_top_up()
+    else {
+        return Ok(());
+    };
+
+    auto_top_up.last_top_up_at = Some(get_time_nanos());
+    deployments_service.set_auto_top_up(deployment_id, Some(auto_top_up))
+}
+
+fn charge_top_up(
+    users_service: &mut UsersService,
+    user_id: UserId,
+    deployment_id: DeploymentId,
+    amount_akt: f64,
+) -> Result<(), ApiError> {
+    users_service.charge_user(
+        user_id,
+        amount_akt,
+        AktLedgerEntryKind::DeploymentDeposit {
+            deployment_id: deployment_id.to_string(),
+        },
+    )
+}
+
+/// Gives back to the user the amount charged for a deposit that failed.
+fn refund_deposit(
+    users_service: &mut UsersService,
+    user_id: UserId,
+    deployment_id: DeploymentId,
+    amount_akt: f64,
+) {
+    if let Err(e) = users_service.credit_user(
+        user_id,
+        amount_akt,
+        AktLedgerEntryKind::DeploymentDepositRefund {
+            deployment_id: deployment_id.to_string(),
+        },
+    ) {
+        log_error!(
+            format!(
+                "[Deployment {}]: Could not refund the failed deposit of {} AKT: {:?}",
+                deployment_id, amount_akt, e
+            ),
+            "refund_deposit"
+        );
+    }
+}
+
+/// Stops topping up the escrow of the deployment and alerts its owner.
+fn stop_auto_top_up(
+    deployments_service: &mut DeploymentsService,
+    deployment_id: DeploymentId,
+    mut auto_top_up: AutoTopUp,
+    reason: String,
+) -> Result<(), ApiError> {
+    log_warn!(
+        format!(
+            "[Deployment {}]: Auto top-up stopped: {}",
+            deployment_id, reason
+        ),
+        "stop_auto_top_up"
+    );
+
+    auto_top_up.stopped_reason = Some(reason.clone());
+    deployments_service.set_auto_top_up(deployment_id, Some(auto_top_up))?;
+
+    deployments_service
+        .alert_deployment_owner(deployment_id, format!("Auto top-up stopped: {}", reason))
+}
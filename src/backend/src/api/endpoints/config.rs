@@ -0,0 +1,61 @@
+use candid::Principal;
+use ic_cdk::{caller, query, update};
+
+use crate::api::{AccessControlService, ApiError, ApiResult, ConfigService, ResourceTiers};
+
+#[query]
+fn get_resource_tiers() -> ApiResult<ResourceTiers> {
+    ConfigEndpoints::default().get_resource_tiers().into()
+}
+
+#[update]
+fn set_resource_tiers(resource_tiers: ResourceTiers) -> ApiResult<()> {
+    let calling_principal = caller();
+
+    ConfigEndpoints::default()
+        .set_resource_tiers(calling_principal, resource_tiers)
+        .into()
+}
+
+#[update]
+fn set_per_user_accounts(per_user_accounts: bool) -> ApiResult<()> {
+    let calling_principal = caller();
+
+    ConfigEndpoints::default()
+        .set_per_user_accounts(calling_principal, per_user_accounts)
+        .into()
+}
+
+#[derive(Default)]
+struct ConfigEndpoints {
+    access_control_service: AccessControlService,
+    config_service: ConfigService,
+}
+
+impl ConfigEndpoints {
+    fn get_resource_tiers(&self) -> Result<ResourceTiers, ApiError> {
+        Ok(self.config_service.get_resource_tiers())
+    }
+
+    fn set_resource_tiers(
+        &mut self,
+        calling_principal: Principal,
+        resource_tiers: ResourceTiers,
+    ) -> Result<(), ApiError> {
+        self.access_control_service
+            .assert_principal_is_admin(&calling_principal)?;
+
+        self.config_service.set_resource_tiers(resource_tiers)
+    }
+
+    fn set_per_user_accounts(
+        &mut self,
+        calling_principal: Principal,
+        per_user_accounts: bool,
+    ) -> Result<(), ApiError> {
+        self.access_control_service
+            .assert_principal_is_admin(&calling_principal)?;
+
+        self.config_service.set_per_user_accounts(per_user_accounts)
+    }
+}
@@ -5,13 +5,24 @@ use ic_cdk::{caller, query, update};
 use utils::base64_encode;
 
 use crate::{
-    akash::{address::get_account_id_from_public_key, bids::fetch_bids, sdl::SdlV3},
+    akash::{
+        bids::{bid_order, fetch_bids, select_bid, OPEN_BID_STATE},
+        proto::market::{
+            bid::{BidId, State as BidState},
+            lease::LeaseId,
+        },
+        sdl::SdlV3,
+    },
     api::{
-        log_error, log_info, map_deployment, services::AkashService, AccessControlService,
-        ApiError, ApiResult, CpuSize, Deployment, DeploymentId, DeploymentParams,
-        DeploymentParamsPort, DeploymentState, DeploymentsService, GetDeploymentResponse,
-        LedgerService, LogService, MTlsCertificateData, MemorySize, StorageSize, UpdateUserInput,
-        UserId, UsersService,
+        accepting_orders_state_mut, log_error, log_info, log_warn, map_deployment,
+        map_deployment_revision,
+        services::{AkashOperation, AkashService},
+        AccessControlService, AktLedgerEntryKind, ApiError, ApiResult, AutoTopUp,
+        AutoTopUpSettings, CpuSize, Deployment, DeploymentBid, DeploymentId, DeploymentLeaseStatus,
+        DeploymentParams, DeploymentParamsPort, DeploymentRevision, DeploymentState,
+        DeploymentsService, GetDeploymentManifestResponse, GetDeploymentResponse, LedgerService,
+        LogService, MTlsCertificateData, MemorySize, OrderSeq, PendingClosesService,
+        PendingDeposit, ResourceTiers, StorageSize, TxError, UpdateUserInput, UserId, UsersService,
     },
     fixtures::example_sdl,
     helpers::uakt_to_akt,
@@ -20,6 +31,45 @@ use crate::{
 const POLLING_BIDS_INTERVAL_SECS: u64 = 3;
 const MAX_FETCH_BIDS_RETRIES: u64 = 5;
 
+/// Held while accepting the bids on some orders of a deployment.
+///
+/// Released when dropped, which also happens when the task traps after a call.
+struct AcceptingOrdersLock {
+    deployment_id: DeploymentId,
+    orders: Vec<OrderSeq>,
+}
+
+impl AcceptingOrdersLock {
+    /// Locks the orders, unless the bids on any of them are already being accepted.
+    fn try_acquire(deployment_id: DeploymentId, orders: Vec<OrderSeq>) -> Option<Self> {
+        accepting_orders_state_mut(|state| {
+            if orders
+                .iter()
+                .any(|order| state.contains(&(deployment_id, *order)))
+            {
+                return None;
+            }
+
+            state.extend(orders.iter().map(|order| (deployment_id, *order)));
+
+            Some(Self {
+                deployment_id,
+                orders,
+            })
+        })
+    }
+}
+
+impl Drop for AcceptingOrdersLock {
+    fn drop(&mut self) {
+        accepting_orders_state_mut(|state| {
+            for order in &self.orders {
+                state.remove(&(self.deployment_id, *order));
+            }
+        });
+    }
+}
+
 #[query]
 fn get_deployment(deployment_id: String) -> ApiResult<GetDeploymentResponse> {
     let calling_principal = caller();
@@ -47,6 +97,21 @@ fn get_deployments() -> ApiResult<Vec<GetDeploymentResponse>> {
         .into()
 }
 
+#[query]
+fn get_deployment_manifest(
+    deployment_id: String,
+    revision: Option<u32>,
+) -> ApiResult<GetDeploymentManifestResponse> {
+    let calling_principal = caller();
+
+    DeploymentsEndpoints::default()
+        .get_deployment_manifest(&calling_principal, &deployment_id, revision)
+        .map(|(revision, deployment_revision)| {
+            map_deployment_revision(revision, deployment_revision)
+        })
+        .into()
+}
+
 #[update]
 async fn create_certificate(cert_data: MTlsCertificateData) -> ApiResult<String> {
     let calling_principal = caller();
@@ -68,6 +133,27 @@ async fn create_deployment(sdl_params: DeploymentParams) -> ApiResult<String> {
         .into()
 }
 
+#[update]
+async fn create_deployment_from_sdl(sdl: String) -> ApiResult<String> {
+    let calling_principal = caller();
+
+    DeploymentsEndpoints::default()
+        .create_deployment_from_sdl(calling_principal, sdl)
+        .await
+        .map(|id| id.to_string())
+        .into()
+}
+
+#[update]
+async fn update_deployment_sdl(deployment_id: String, sdl: String) -> ApiResult<()> {
+    let calling_principal = caller();
+
+    DeploymentsEndpoints::default()
+        .update_deployment_sdl(calling_principal, deployment_id, sdl)
+        .await
+        .into()
+}
+
 #[update]
 async fn update_deployment_state(deployment_id: String, update: DeploymentState) -> ApiResult<()> {
     let calling_principal = caller();
@@ -107,6 +193,16 @@ async fn create_test_deployment() -> ApiResult<String> {
         .into()
 }
 
+#[update]
+async fn get_deployment_lease(deployment_id: String) -> ApiResult<DeploymentLeaseStatus> {
+    let calling_principal = caller();
+
+    DeploymentsEndpoints::default()
+        .get_deployment_lease(calling_principal, deployment_id)
+        .await
+        .into()
+}
+
 #[update]
 async fn deposit_deployment(deployment_id: String, amount_uakt: u64) -> ApiResult<()> {
     let calling_principal = caller();
@@ -117,6 +213,18 @@ async fn deposit_deployment(deployment_id: String, amount_uakt: u64) -> ApiResul
         .into()
 }
 
+#[update]
+fn set_deployment_auto_top_up(
+    deployment_id: String,
+    settings: Option<AutoTopUpSettings>,
+) -> ApiResult<()> {
+    let calling_principal = caller();
+
+    DeploymentsEndpoints::default()
+        .set_deployment_auto_top_up(calling_principal, deployment_id, settings)
+        .into()
+}
+
 #[update]
 async fn update_test_deployment_sdl(deployment_id: String) -> ApiResult<()> {
     let calling_principal = caller();
@@ -128,6 +236,16 @@ async fn update_test_deployment_sdl(deployment_id: String) -> ApiResult<()> {
         .into()
 }
 
+#[update]
+async fn accept_bid(deployment_id: String, provider: String) -> ApiResult<()> {
+    let calling_principal = caller();
+
+    DeploymentsEndpoints::default()
+        .accept_bid(calling_principal, deployment_id, provider)
+        .await
+        .into()
+}
+
 #[update]
 async fn close_deployment(deployment_id: String) -> ApiResult<()> {
     let calling_principal = caller();
@@ -139,9 +257,9 @@ async fn close_deployment(deployment_id: String) -> ApiResult<()> {
 }
 
 #[update]
-async fn get_deployment_icp_price() -> ApiResult<f64> {
+async fn get_deployment_icp_price(sdl_params: DeploymentParams) -> ApiResult<f64> {
     DeploymentsEndpoints::default()
-        .get_deployment_icp_price()
+        .get_deployment_icp_price(sdl_params)
         .await
         .into()
 }
@@ -186,6 +304,39 @@ impl DeploymentsEndpoints {
         Ok(deployments)
     }
 
+    /// Returns the requested revision of the deployment, or the latest one if not specified.
+    fn get_deployment_manifest(
+        &self,
+        calling_principal: &Principal,
+        deployment_id: &str,
+        revision: Option<u32>,
+    ) -> Result<(u32, DeploymentRevision), ApiError> {
+        let revisions = self
+            .get_deployment(calling_principal, deployment_id)?
+            .revisions();
+
+        let revision = match revision {
+            Some(revision) => revision,
+            None => (revisions.len() as u32).checked_sub(1).ok_or_else(|| {
+                ApiError::not_found(&format!(
+                    "Deployment {} has not been created on Akash",
+                    deployment_id
+                ))
+            })?,
+        };
+
+        revisions
+            .get(revision as usize)
+            .cloned()
+            .map(|deployment_revision| (revision, deployment_revision))
+            .ok_or_else(|| {
+                ApiError::not_found(&format!(
+                    "Revision {} of deployment {} not found",
+                    revision, deployment_id
+                ))
+            })
+    }
+
     async fn create_certificate(
         &mut self,
         calling_principal: Principal,
@@ -197,14 +348,19 @@ impl DeploymentsEndpoints {
         let cert_pem_base64 = base64_encode(&cert_data.cert);
         let pub_key_pem_base64 = base64_encode(&cert_data.pub_key);
 
-        let tx_hash = self
-            .akash_service
+        let user_id = UserId::new(calling_principal);
+        // the certificate must be owned by the account that owns the user's deployments
+        let akash_account = self
+            .deployments_service
+            .get_config()
+            .user_akash_account(user_id);
+
+        let tx_hash = AkashService::default()
+            .with_account(akash_account)
             .create_certificate(cert_pem_base64, pub_key_pem_base64)
             .await
             .map_err(|e| ApiError::internal(&format!("Error creating certificate: {}", e)))?;
 
-        let user_id = UserId::new(calling_principal);
-
         self.users_service.update_user(
             user_id,
             UpdateUserInput {
@@ -223,8 +379,48 @@ impl DeploymentsEndpoints {
         self.access_control_service
             .assert_principal_is_user(&calling_principal)?;
 
-        let deployment_akt_price = self.deployments_service.get_deployment_akt_price();
-        let deployment_icp_price = self.get_deployment_icp_price().await?;
+        let parsed_sdl = SdlV3::try_from_deployment_params(
+            sdl_params.clone(),
+            self.deployments_service.get_config().resource_tiers(),
+        )
+        .map_err(|e| ApiError::invalid_argument(&format!("Invalid SDL: {}", e)))?;
+        let sdl = parsed_sdl
+            .to_yaml()
+            .map_err(|e| ApiError::internal(&format!("Could not serialize SDL: {}", e)))?;
+
+        self.start_deployment(calling_principal, Some(sdl_params), sdl, parsed_sdl)
+            .await
+    }
+
+    async fn create_deployment_from_sdl(
+        &mut self,
+        calling_principal: Principal,
+        sdl: String,
+    ) -> Result<DeploymentId, ApiError> {
+        self.access_control_service
+            .assert_principal_is_user(&calling_principal)?;
+
+        let parsed_sdl = SdlV3::try_from_str(&sdl)
+            .map_err(|e| ApiError::invalid_argument(&format!("Invalid SDL: {}", e)))?;
+
+        self.start_deployment(calling_principal, None, sdl, parsed_sdl)
+            .await
+    }
+
+    /// Charges the user for the SDL and starts creating the deployment on Akash.
+    async fn start_deployment(
+        &mut self,
+        calling_principal: Principal,
+        sdl_params: Option<DeploymentParams>,
+        sdl: String,
+        parsed_sdl: SdlV3,
+    ) -> Result<DeploymentId, ApiError> {
+        let duration_secs = sdl_params.as_ref().and_then(|params| params.duration_secs);
+        let deployment_price = self
+            .deployments_service
+            .get_deployment_price(&parsed_sdl, duration_secs)?;
+        let deployment_akt_price = deployment_price.akt;
+        let deployment_icp_price = self.akt_to_icp(deployment_akt_price).await?;
 
         let canister_balance = self
             .akash_service
@@ -239,13 +435,14 @@ impl DeploymentsEndpoints {
             )));
         }
 
-        let parsed_sdl = SdlV3::try_from_deployment_params(sdl_params.clone())
-            .map_err(|e| ApiError::invalid_argument(&format!("Invalid SDL: {}", e)))?;
-
         let user_id = UserId::new(calling_principal);
         // deduct AKT from user's balance for deployment escrow
         self.users_service
-            .charge_user(user_id, deployment_akt_price)
+            .charge_user(
+                user_id,
+                deployment_akt_price,
+                AktLedgerEntryKind::DeploymentCreation,
+            )
             .map_err(|e| {
                 if e.message().contains("Not enough AKT balance.") {
                     ApiError::permission_denied(&format!(
@@ -262,7 +459,8 @@ impl DeploymentsEndpoints {
             .init_deployment(
                 user_id,
                 sdl_params,
-                deployment_akt_price,
+                sdl,
+                deployment_price,
                 deployment_icp_price,
             )
             .await?;
@@ -298,6 +496,36 @@ impl DeploymentsEndpoints {
         Ok(deployment_id)
     }
 
+    async fn get_deployment_lease(
+        &self,
+        calling_principal: Principal,
+        deployment_id: String,
+    ) -> Result<DeploymentLeaseStatus, ApiError> {
+        let deployment_id = DeploymentId::try_from(&deployment_id[..])
+            .map_err(|e| ApiError::invalid_argument(&format!("Invalid deployment id: {}", e)))?;
+
+        self.access_control_service
+            .assert_principal_owns_deployment(&calling_principal, &deployment_id)?;
+
+        let deployment = self.deployments_service.get_deployment(&deployment_id)?;
+        let dseq = deployment
+            .get_akash_info()
+            .ok_or(ApiError::not_found(&format!(
+                "Deployment {} is initialized but has not been created",
+                deployment_id
+            )))?;
+
+        AkashService::default()
+            .with_account(deployment.akash_account())
+            .lease_status(dseq)
+            .await
+            .map_err(|e| ApiError::internal(&format!("Error fetching lease: {}", e)))?
+            .ok_or(ApiError::not_found(&format!(
+                "Deployment {} not found on Akash",
+                deployment_id
+            )))
+    }
+
     async fn deposit_deployment(
         &mut self,
         calling_principal: Principal,
@@ -310,25 +538,76 @@ impl DeploymentsEndpoints {
         self.access_control_service
             .assert_principal_owns_deployment(&calling_principal, &deployment_id)?;
 
-        // TODO: check if deployment is not closed or failed
+        let deployment = self.deployments_service.get_deployment(&deployment_id)?;
+        if deployment.state().is_terminal() {
+            return Err(ApiError::conflict(&format!(
+                "Cannot deposit to deployment in {} state",
+                deployment.state().name()
+            )));
+        }
 
-        let dseq = self
-            .deployments_service
-            .get_akash_deployment_info(&deployment_id)?
+        let dseq = deployment
+            .get_akash_info()
             .ok_or(ApiError::not_found(&format!(
                 "Deployment {} is initialized but has not been created",
                 deployment_id
             )))?;
 
-        self.akash_service
-            .deposit_deployment(dseq, amount_uakt)
-            .await
-            .map_err(|e| ApiError::internal(&format!("Error updating deployment: {}", e)))?;
-
         let user_id = UserId::new(calling_principal);
-        // deduct AKT from user's balance for deposit to deployment escrow
-        self.users_service
-            .charge_user(user_id, uakt_to_akt(amount_uakt))?;
+        let amount_akt = uakt_to_akt(amount_uakt);
+        // deduct AKT from user's balance for deposit to deployment escrow,
+        // before depositing so that the balance cannot change in between
+        self.users_service.charge_user(
+            user_id,
+            amount_akt,
+            AktLedgerEntryKind::DeploymentDeposit {
+                deployment_id: deployment_id.to_string(),
+            },
+        )?;
+
+        match AkashService::default()
+            .with_account(deployment.akash_account())
+            .deposit_deployment(deployment_id, dseq, amount_uakt)
+            .await
+        {
+            Ok(()) => {}
+            Err(TxError::Failed(e)) => {
+                self.users_service.credit_user(
+                    user_id,
+                    amount_akt,
+                    AktLedgerEntryKind::DeploymentDepositRefund {
+                        deployment_id: deployment_id.to_string(),
+                    },
+                )?;
+
+                return Err(ApiError::internal(&format!(
+                    "Error updating deployment: {}",
+                    e
+                )));
+            }
+            // the deposit may still be applied, so the user is refunded
+            // by the auto top-up job only once it has definitely failed
+            Err(TxError::Unknown {
+                tx_hash,
+                timeout_height,
+                reason,
+            }) => {
+                self.deployments_service.add_pending_deposit(
+                    deployment_id,
+                    PendingDeposit {
+                        tx_hash: tx_hash.clone(),
+                        timeout_height,
+                        amount_uakt,
+                        is_auto_top_up: false,
+                    },
+                )?;
+
+                return Err(ApiError::internal(&format!(
+                    "Deposit {} not confirmed yet, it is refunded if it fails: {}",
+                    tx_hash, reason
+                )));
+            }
+        }
 
         self.log_service.log_info(
             format!("[Deployment {}]: Deposit deployment", deployment_id),
@@ -337,8 +616,39 @@ impl DeploymentsEndpoints {
         Ok(())
     }
 
+    fn set_deployment_auto_top_up(
+        &mut self,
+        calling_principal: Principal,
+        deployment_id: String,
+        settings: Option<AutoTopUpSettings>,
+    ) -> Result<(), ApiError> {
+        let deployment_id = DeploymentId::try_from(&deployment_id[..])
+            .map_err(|e| ApiError::invalid_argument(&format!("Invalid deployment id: {}", e)))?;
+
+        self.access_control_service
+            .assert_principal_owns_deployment(&calling_principal, &deployment_id)?;
+
+        if let Some(settings) = &settings {
+            settings.validate().map_err(|e| {
+                ApiError::invalid_argument(&format!("Invalid auto top-up settings: {}", e))
+            })?;
+        }
+
+        let deployment = self.deployments_service.get_deployment(&deployment_id)?;
+        if deployment.state().is_terminal() {
+            return Err(ApiError::conflict(&format!(
+                "Cannot set auto top-up of deployment in {} state",
+                deployment.state().name()
+            )));
+        }
+
+        // setting the auto top-up again also restarts it after it has been stopped
+        self.deployments_service
+            .set_auto_top_up(deployment_id, settings.map(AutoTopUp::new))
+    }
+
     async fn update_deployment_sdl(
-        &self,
+        &mut self,
         calling_principal: Principal,
         deployment_id: String,
         sdl: String,
@@ -352,21 +662,57 @@ impl DeploymentsEndpoints {
         let parsed_sdl = SdlV3::try_from_str(&sdl)
             .map_err(|e| ApiError::invalid_argument(&format!("Invalid SDL: {}", e)))?;
 
-        // no need to deduct AKT from user's balance for udating deplyment as the needed tokens are taken from the escrow contract
+        let deployment = self.deployments_service.get_deployment(&deployment_id)?;
 
-        let dseq = self
-            .deployments_service
-            .get_akash_deployment_info(&deployment_id)?
+        // only the name of the target state is checked, its fields are set once the update is on chain
+        deployment.check_transition(&DeploymentState::Updated {
+            tx_hash: String::new(),
+            manifest_version: String::new(),
+        })?;
+
+        let dseq = deployment
+            .get_akash_info()
             .ok_or(ApiError::not_found(&format!(
                 "Deployment with id {} is initialized but has not been created",
                 deployment_id
             )))?;
 
-        self.akash_service
-            .update_deployment_sdl(dseq, parsed_sdl)
+        let current_sdl = current_sdl(
+            &deployment,
+            self.deployments_service.get_config().resource_tiers(),
+        )?;
+        current_sdl
+            .check_update_compatibility(&parsed_sdl)
+            .map_err(|e| ApiError::invalid_argument(&format!("Incompatible SDL: {}", e)))?;
+
+        let manifest_version = parsed_sdl.manifest_version();
+        let manifest = parsed_sdl.manifest_sorted_json();
+
+        // the resources cannot change, so the escrow already covers the updated deployment
+        let result = AkashService::default()
+            .with_account(deployment.akash_account())
+            .execute_batch(
+                Some(deployment_id),
+                vec![AkashOperation::UpdateDeploymentSdl {
+                    sdl: parsed_sdl,
+                    dseq,
+                }],
+            )
             .await
             .map_err(|e| ApiError::internal(&format!("Error updating deployment: {}", e)))?;
 
+        self.deployments_service.set_updated_deployment(
+            calling_principal,
+            deployment_id,
+            result.hash,
+            sdl.clone(),
+            manifest,
+            &manifest_version,
+        )?;
+
+        self.deployments_service
+            .update_deployment_sdl(deployment_id, sdl)?;
+
         self.log_service.log_info(
             format!("[Deployment {}]: Updated deployment", deployment_id),
             None,
@@ -387,18 +733,10 @@ impl DeploymentsEndpoints {
             .assert_principal_owns_deployment(&calling_principal, &deployment_id)?;
 
         // the client can only update the deployment state only after a lease has been created
-        match self
-            .deployments_service
+        // or after the deployment has been updated, once it has sent the manifest to the providers
+        self.deployments_service
             .get_deployment(&deployment_id)?
-            .state()
-        {
-            DeploymentState::LeaseCreated { .. } => {}
-            _ => {
-                return Err(ApiError::invalid_argument(
-                    "Deployment must be in LeaseCreated state",
-                ));
-            }
-        }
+            .check_transition(&update)?;
 
         match update {
             DeploymentState::Active => self.deployments_service.update_deployment_state(
@@ -430,6 +768,123 @@ impl DeploymentsEndpoints {
         }
     }
 
+    async fn accept_bid(
+        &mut self,
+        calling_principal: Principal,
+        deployment_id: String,
+        provider: String,
+    ) -> Result<(), ApiError> {
+        let deployment_id = DeploymentId::try_from(&deployment_id[..])
+            .map_err(|e| ApiError::invalid_argument(&format!("Invalid deployment id: {}", e)))?;
+
+        self.access_control_service
+            .assert_principal_owns_deployment(&calling_principal, &deployment_id)?;
+
+        let deployment = self.deployments_service.get_deployment(&deployment_id)?;
+
+        // with multiple orders, the bids on the remaining orders can be accepted
+        // once the bids on some of them have already been accepted.
+        // Only the name of the target state is checked, its fields are set once the lease is on chain
+        deployment.check_transition(&DeploymentState::LeaseCreated {
+            tx_hash: String::new(),
+            provider_url: String::new(),
+            order: None,
+            selection_reason: None,
+        })?;
+
+        let pending_orders = deployment.pending_orders();
+        let bids = deployment
+            .received_bids()
+            .into_iter()
+            .filter(|bid| {
+                bid.provider == provider && pending_orders.contains(&(bid.gseq, bid.oseq))
+            })
+            .collect::<Vec<_>>();
+
+        if bids.is_empty() {
+            return Err(ApiError::not_found(&format!(
+                "No bid received from provider {} on the orders still without a lease",
+                provider
+            )));
+        }
+
+        let dseq = deployment
+            .get_akash_info()
+            .ok_or(ApiError::not_found(&format!(
+                "Deployment {} is initialized but has not been created",
+                deployment_id
+            )))?;
+        // the lock is released when dropped, also on the errors below
+        let _lock = AcceptingOrdersLock::try_acquire(
+            deployment_id,
+            bids.iter().map(|bid| (bid.gseq, bid.oseq)).collect(),
+        )
+        .ok_or(ApiError::conflict(
+            "The bids on these orders are already being accepted",
+        ))?;
+
+        let akash_service = AkashService::default().with_account(deployment.akash_account());
+        let owner = akash_service
+            .address()
+            .await
+            .map_err(|e| ApiError::internal(&format!("failed to get address: {}", e)))?;
+
+        // the provider's bids on all the pending orders are accepted.
+        // A failure is returned to the user, who can accept another bid,
+        // as the deployment and the leases already created are still valid
+        for bid in bids {
+            let bid_id = BidId {
+                owner: owner.clone(),
+                dseq,
+                gseq: bid.gseq,
+                oseq: bid.oseq,
+                provider: bid.provider,
+            };
+
+            // the provider may have closed its bid since it was received, e.g. after its bid timeout
+            let Some(bid) = akash_service
+                .bid(bid_id.clone())
+                .await
+                .map_err(|e| ApiError::internal(&format!("Error fetching bid: {}", e)))?
+            else {
+                return Err(ApiError::conflict(&format!(
+                    "Bid of provider {} on order {}/{} no longer exists",
+                    bid_id.provider, bid_id.gseq, bid_id.oseq
+                )));
+            };
+            // an active bid already has a lease, creating another one would fail on chain
+            if bid.state() != BidState::BidOpen {
+                return Err(ApiError::conflict(&format!(
+                    "Bid of provider {} on order {}/{} is in state {:?}, only open bids can be accepted",
+                    bid_id.provider,
+                    bid_id.gseq,
+                    bid_id.oseq,
+                    bid.state()
+                )));
+            }
+
+            // the bid has been chosen by the user, there is no selection reason to record
+            handle_create_lease(
+                &akash_service,
+                calling_principal,
+                bid_id,
+                deployment_id,
+                None,
+            )
+            .await?;
+        }
+
+        self.log_service.log_info(
+            format!(
+                "[Deployment {}]: Accepted bid from provider {}",
+                deployment_id, provider
+            ),
+            Some("accept_bid".to_string()),
+        )?;
+
+        Ok(())
+    }
+
     async fn close_deployment(
         &mut self,
         calling_principal: Principal,
@@ -451,17 +906,29 @@ impl DeploymentsEndpoints {
                 e.message().to_string(),
             )?;
         } else {
+            // the AKT remaining in the escrow are added back to the user's balance
+            // once the pending closes job confirms the closure on chain
             self.log_service
                 .log_info(format!("[Deployment {}]: Closed", deployment_id), None)?;
-            // TODO: add back to the user's balance the AKT tokens remaining from the escrow
         }
         Ok(())
     }
 
-    async fn get_deployment_icp_price(&self) -> Result<f64, ApiError> {
-        let deployment_akt_price = self.deployments_service.get_deployment_akt_price();
+    async fn get_deployment_icp_price(
+        &self,
+        sdl_params: DeploymentParams,
+    ) -> Result<f64, ApiError> {
+        let parsed_sdl = SdlV3::try_from_deployment_params(
+            sdl_params.clone(),
+            self.deployments_service.get_config().resource_tiers(),
+        )
+        .map_err(|e| ApiError::invalid_argument(&format!("Invalid SDL: {}", e)))?;
+
+        let deployment_price = self
+            .deployments_service
+            .get_deployment_price(&parsed_sdl, sdl_params.duration_secs)?;
 
-        self.akt_to_icp(deployment_akt_price).await
+        self.akt_to_icp(deployment_price.akt).await
     }
 
     async fn akt_to_icp(&self, amount_akt: f64) -> Result<f64, ApiError> {
@@ -471,6 +938,122 @@ impl DeploymentsEndpoints {
     }
 }
 
+/// Parses the SDL the deployment is currently running.
+fn current_sdl(deployment: &Deployment, resource_tiers: &ResourceTiers) -> Result<SdlV3, ApiError> {
+    match (deployment.sdl(), deployment.params()) {
+        (Some(sdl), _) => SdlV3::try_from_str(&sdl),
+        // deployments created before the SDL was stored
+        (None, Some(params)) => SdlV3::try_from_deployment_params(params, resource_tiers),
+        (None, None) => Err(String::from("deployment has no SDL")),
+    }
+    .map_err(|e| ApiError::internal(&format!("Invalid current SDL: {}", e)))
+}
+
+/// Re-schedules the steps of the deployments whose creation was in progress.
+///
+/// Timers do not survive upgrades, so this must be called in `post_upgrade`.
+pub fn resume_deployments() {
+    let deployments_service = DeploymentsService::default();
+    let resource_tiers = deployments_service.get_config().resource_tiers().clone();
+
+    for (deployment_id, deployment) in deployments_service.get_deployments_in_progress() {
+        let calling_principal = deployment.user_id().principal();
+
+        // with manual selection, the bids already handed over must be accepted by the user
+        let awaits_user =
+            deployment.is_manual_bid_selection() && !deployment.received_bids().is_empty();
+
+        match (deployment.state(), deployment.get_akash_info()) {
+            (DeploymentState::Initialized, _) => {
+                let parsed_sdl = match current_sdl(&deployment, &resource_tiers) {
+                    Ok(parsed_sdl) => parsed_sdl,
+                    Err(e) => {
+                        log_error!(
+                            format!(
+                                "[Deployment {}]: Cannot resume deployment: {:?}",
+                                deployment_id, e
+                            ),
+                            "resume_deployments"
+                        );
+                        continue;
+                    }
+                };
+
+                log_info!(
+                    format!("[Deployment {}]: Resuming creation", deployment_id),
+                    "resume_deployments"
+                );
+
+                ic_cdk_timers::set_timer(Duration::ZERO, move || {
+                    ic_cdk::spawn(async move {
+                        if let Err(e) =
+                            handle_deployment(calling_principal, parsed_sdl, deployment_id).await
+                        {
+                            set_failed_deployment_with_close(
+                                deployment_id,
+                                calling_principal,
+                                format!("Error handling deployment: {:?}", e),
+                            )
+                            .await;
+                        }
+                    });
+                });
+            }
+            // the bids of the orders still without a lease must be polled again,
+            // unless the user has to accept them
+            (_, Some(dseq)) if !deployment.pending_orders().is_empty() && !awaits_user => {
+                log_info!(
+                    format!("[Deployment {}]: Resuming lease creation", deployment_id),
+                    "resume_deployments"
+                );
+
+                ic_cdk_timers::set_timer(Duration::ZERO, move || {
+                    ic_cdk::spawn(async move {
+                        if let Err(e) = sync_leases(calling_principal, deployment_id, dseq).await {
+                            log_warn!(
+                                format!(
+                                    "[Deployment {}]: Failed to sync the leases: {:?}",
+                                    deployment_id, e
+                                ),
+                                "resume_deployments"
+                            );
+                        }
+
+                        handle_lease(calling_principal, dseq, deployment_id, 0);
+                    });
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Records the leases created on chain for the pending orders, which were not stored
+/// because the canister was upgraded while creating them.
+async fn sync_leases(
+    calling_principal: Principal,
+    deployment_id: DeploymentId,
+    dseq: u64,
+) -> Result<(), ApiError> {
+    let deployment = DeploymentsService::default().get_deployment(&deployment_id)?;
+    let pending_orders = deployment.pending_orders();
+
+    let leased_bids = AkashService::default()
+        .with_account(deployment.akash_account())
+        .leased_bids(dseq)
+        .await
+        .map_err(|e| ApiError::internal(&format!("Error fetching leased bids: {}", e)))?;
+
+    for bid_id in leased_bids {
+        if pending_orders.contains(&(bid_id.gseq, bid_id.oseq)) {
+            // the reason of the selection has been lost with the lease
+            record_existing_lease(calling_principal, bid_id, deployment_id, None).await?;
+        }
+    }
+
+    Ok(())
+}
+
 async fn handle_deployment(
     calling_principal: Principal,
     parsed_sdl: SdlV3,
@@ -495,36 +1078,90 @@ async fn handle_create_deployment(
     parsed_sdl: SdlV3,
     deployment_id: DeploymentId,
 ) -> Result<u64, ApiError> {
-    let mut akash_service = AkashService::default();
+    // always spawned from a timer
     let mut deployment_service = DeploymentsService::default();
 
-    let (tx_hash, dseq, manifest) = akash_service
-        .create_deployment(parsed_sdl.clone())
-        .await
-        .map_err(|e| ApiError::internal(&format!("Error creating deployment: {}", e)))?;
+    let deployment = deployment_service.get_deployment(&deployment_id)?;
+    let akash_service = AkashService::default()
+        .with_timer_polling()
+        .with_account(deployment.akash_account());
+    let escrow_deposit_uakt = deployment.escrow_deposit_uakt().unwrap_or(
+        deployment_service
+            .get_config()
+            .akash_config()
+            .min_deposit_uakt_amount,
+    );
+
+    // the dseq is reserved before broadcasting, so that a retry does not create a second deployment
+    let (dseq, is_retry) = match deployment.reserved_dseq() {
+        Some(dseq) => (dseq, true),
+        None => {
+            let dseq = akash_service
+                .next_deployment_id()
+                .await
+                .map_err(|e| ApiError::internal(&format!("Error reserving dseq: {}", e)))?;
+            deployment_service.reserve_deployment_dseq(deployment_id, dseq)?;
+            (dseq, false)
+        }
+    };
+
+    let already_created = is_retry
+        && akash_service
+            .deployment_exists(dseq)
+            .await
+            .map_err(|e| ApiError::internal(&format!("Error fetching deployment: {}", e)))?;
+
+    let (tx_hash, manifest) = if already_created {
+        log_warn!(
+            format!(
+                "[Deployment {}]: Deployment with dseq {} already created on Akash",
+                deployment_id, dseq
+            ),
+            "handle_create_deployment"
+        );
+
+        // the hash of the transaction that created the deployment has been lost
+        (None, parsed_sdl.manifest_sorted_json())
+    } else {
+        let (tx_hash, _, manifest) = akash_service
+            .create_deployment(deployment_id, parsed_sdl.clone(), dseq, escrow_deposit_uakt)
+            .await
+            .map_err(|e| ApiError::internal(&format!("Error creating deployment: {}", e)))?;
+
+        (Some(tx_hash), manifest)
+    };
 
     let deployment_update = DeploymentState::DeploymentCreated {
         tx_hash: tx_hash.clone(),
         dseq,
         manifest_sorted_json: manifest.clone(),
+        groups: Some(parsed_sdl.groups().len() as u32),
     };
 
     deployment_service
         .update_deployment_state(calling_principal, deployment_id, deployment_update, true)
         .map_err(|e| ApiError::internal(&format!("Error updating deployment: {:?}", e)))?;
 
-    let sdl_yaml = parsed_sdl
-        .to_yaml()
-        .map_err(|e| ApiError::internal(&format!("Error serializing SDL to YAML: {}", e)))?;
+    // deployments created before the SDL was stored have their SDL rendered from the params
+    let sdl_yaml = match deployment.sdl() {
+        Some(sdl) => sdl,
+        None => parsed_sdl
+            .to_yaml()
+            .map_err(|e| ApiError::internal(&format!("Error serializing SDL to YAML: {}", e)))?,
+    };
+    let revision =
+        DeploymentRevision::new(tx_hash, sdl_yaml, manifest, &parsed_sdl.manifest_version());
 
     log_info!(
         format!(
-            "[Deployment {}]: Created deployment. YAML: {}, with manifest: {}",
-            deployment_id, sdl_yaml, manifest,
+            "[Deployment {}]: Created deployment with manifest version {}",
+            deployment_id, revision.manifest_version,
         ),
         "handle_create_deployment"
     );
 
+    deployment_service.add_deployment_revision(deployment_id, revision)?;
+
     Ok(dseq)
 }
 
@@ -532,16 +1169,26 @@ fn handle_lease(calling_principal: Principal, dseq: u64, deployment_id: Deployme
     ic_cdk_timers::set_timer(Duration::from_secs(POLLING_BIDS_INTERVAL_SECS), move || {
         ic_cdk::spawn(async move {
             match try_fetch_bids_and_create_lease(calling_principal, dseq, deployment_id).await {
-                Ok(Some((_tx_hash, deployment_url))) => {
+                Ok(FetchBidsOutcome::LeasesCreated { deployment_urls }) => {
+                    log_info!(
+                        format!(
+                            "[Deployment {}]: Deployment URLs: {}",
+                            deployment_id,
+                            deployment_urls.join(", ")
+                        ),
+                        "handle_lease"
+                    );
+                }
+                Ok(FetchBidsOutcome::BidsReceived) => {
                     log_info!(
                         format!(
-                            "[Deployment {}]: Deployment URL: {}",
-                            deployment_id, deployment_url
+                            "[Deployment {}]: Waiting for the user to accept a bid",
+                            deployment_id
                         ),
                         "handle_lease"
                     );
                 }
-                Ok(None) => {
+                Ok(FetchBidsOutcome::MissingBids) => {
                     if retry > MAX_FETCH_BIDS_RETRIES {
                         log_error!(
                             format!(
@@ -573,38 +1220,44 @@ fn handle_lease(calling_principal: Principal, dseq: u64, deployment_id: Deployme
     });
 }
 
+enum FetchBidsOutcome {
+    /// some orders have not received a bid (satisfying the selection strategy) yet
+    MissingBids,
+    /// the bids have been handed over to the user, who has to accept them
+    BidsReceived,
+    /// all the orders have a lease
+    LeasesCreated { deployment_urls: Vec<String> },
+}
+
 async fn try_fetch_bids_and_create_lease(
     calling_principal: Principal,
     dseq: u64,
     deployment_id: DeploymentId,
-) -> Result<Option<(String, String)>, ApiError> {
+) -> Result<FetchBidsOutcome, ApiError> {
+    let deployment = DeploymentsService::default().get_deployment(&deployment_id)?;
+
     // if the deployment has failed, there is no need to keep fetching bids
-    if let DeploymentState::FailedOnCanister { .. } = DeploymentsService::default()
-        .get_deployment(&deployment_id)?
-        .state()
-    {
+    if let DeploymentState::FailedOnCanister { .. } = deployment.state() {
         return Err(ApiError::internal(
             "Deployment failed. Stopped fetching bids",
         ));
     }
 
     // if the deployment is closed, there is no need to keep fetching bids
-    if let DeploymentState::Closed = DeploymentsService::default()
-        .get_deployment(&deployment_id)?
-        .state()
-    {
+    if let DeploymentState::Closed = deployment.state() {
         return Err(ApiError::internal(
             "Deployment closed. Stopped fetching bids",
         ));
     }
 
-    let akash_service = AkashService::default();
+    // always spawned from a timer
+    let akash_service = AkashService::default()
+        .with_timer_polling()
+        .with_account(deployment.akash_account());
     let config = akash_service.get_config();
-    let public_key = config
-        .public_key()
+    let account_id = akash_service
+        .account_id()
         .await
-        .map_err(|e| ApiError::internal(&format!("failed to get public key: {}", e)))?;
-    let account_id = get_account_id_from_public_key(&public_key)
         .map_err(|e| ApiError::internal(&format!("failed to get account id: {}", e)))?;
     let rpc_url = config.tendermint_rpc_url();
 
@@ -616,41 +1269,198 @@ async fn try_fetch_bids_and_create_lease(
         "try_fetch_bids_and_create_lease"
     );
 
-    let bids = fetch_bids(rpc_url.clone(), &account_id, dseq)
+    let pending_orders = deployment.pending_orders();
+    let bids = fetch_bids(rpc_url.clone(), &account_id, dseq, OPEN_BID_STATE)
         .await
-        .map_err(|e| ApiError::internal(e.as_str()))?;
+        .map_err(|e| ApiError::internal(e.as_str()))?
+        .into_iter()
+        .filter(|bid| bid_order(bid).is_some_and(|order| pending_orders.contains(&order)))
+        .collect::<Vec<_>>();
 
     if bids.is_empty() {
-        return Ok(None);
+        return Ok(FetchBidsOutcome::MissingBids);
     }
 
     log_info!(
-        format!("[Deployment {}]: Bids found", deployment_id),
+        format!(
+            "[Deployment {}]: {} bids found for {} orders",
+            deployment_id,
+            bids.len(),
+            pending_orders.len()
+        ),
         "try_fetch_bids_and_create_lease"
     );
-    let (tx_hash, deployment_url) =
-        handle_create_lease(calling_principal, dseq, deployment_id).await?;
-    Ok(Some((tx_hash, deployment_url)))
+
+    if deployment.is_manual_bid_selection() {
+        // the user is asked to choose only once every order has received a bid
+        if pending_orders
+            .iter()
+            .any(|order| !bids.iter().any(|bid| bid_order(bid) == Some(*order)))
+        {
+            return Ok(FetchBidsOutcome::MissingBids);
+        }
+
+        let bids = bids
+            .into_iter()
+            .filter_map(|b| b.bid)
+            .map(DeploymentBid::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ApiError::internal(&format!("Error decoding bids: {}", e)))?;
+
+        DeploymentsService::default().update_deployment_state(
+            calling_principal,
+            deployment_id,
+            DeploymentState::BidsReceived { bids },
+            true,
+        )?;
+
+        return Ok(FetchBidsOutcome::BidsReceived);
+    }
+
+    let mut deployment_urls = vec![];
+    for (gseq, oseq) in pending_orders {
+        let order_bids = bids
+            .iter()
+            .filter(|bid| bid_order(bid) == Some((gseq, oseq)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if order_bids.is_empty() {
+            continue;
+        }
+
+        let Some(selected_bid) =
+            select_bid(rpc_url.clone(), order_bids, &deployment.bid_selection())
+                .await
+                .map_err(|e| ApiError::internal(&format!("Error selecting bid: {}", e)))?
+        else {
+            log_info!(
+                format!(
+                    "[Deployment {}]: No bid satisfies the selection strategy for order {}/{}",
+                    deployment_id, gseq, oseq
+                ),
+                "try_fetch_bids_and_create_lease"
+            );
+            continue;
+        };
+
+        log_info!(
+            format!(
+                "[Deployment {}]: Bid selected for order {}/{}: {}",
+                deployment_id, gseq, oseq, selected_bid.reason
+            ),
+            "try_fetch_bids_and_create_lease"
+        );
+
+        let bid_id = selected_bid
+            .bid
+            .bid_id
+            .ok_or_else(|| ApiError::internal("Selected bid has no id"))?;
+
+        let (_, deployment_url) = handle_create_lease(
+            &akash_service,
+            calling_principal,
+            bid_id,
+            deployment_id,
+            Some(selected_bid.reason),
+        )
+        .await?;
+        deployment_urls.push(deployment_url);
+    }
+
+    // the orders that are still missing a lease are retried
+    if !DeploymentsService::default()
+        .get_deployment(&deployment_id)?
+        .pending_orders()
+        .is_empty()
+    {
+        return Ok(FetchBidsOutcome::MissingBids);
+    }
+
+    Ok(FetchBidsOutcome::LeasesCreated { deployment_urls })
 }
 
 async fn handle_create_lease(
+    akash_service: &AkashService,
     calling_principal: Principal,
-    dseq: u64,
+    bid_id: BidId,
     deployment_id: DeploymentId,
+    selection_reason: Option<String>,
 ) -> Result<(String, String), ApiError> {
-    let akash_service = AkashService::default();
-    let mut deployment_service = DeploymentsService::default();
+    // the lease may have been created by a previous attempt whose result was lost
+    let existing_lease = akash_service
+        .lease(LeaseId::from(&bid_id))
+        .await
+        .map_err(|e| ApiError::internal(&format!("Error fetching lease: {}", e)))?;
+    if existing_lease.is_some() {
+        return record_existing_lease(calling_principal, bid_id, deployment_id, selection_reason)
+            .await;
+    }
 
+    let order = (bid_id.gseq, bid_id.oseq);
     let (tx_hash, provider_url) = akash_service
-        .create_lease(dseq)
+        .create_lease(deployment_id, bid_id)
         .await
         .map_err(|e| ApiError::internal(&format!("Error creating lease: {}", e)))?;
 
+    set_lease_created(
+        calling_principal,
+        deployment_id,
+        order,
+        tx_hash,
+        provider_url,
+        selection_reason,
+    )
+}
+
+/// Records the lease created on chain without the canister knowing it,
+/// e.g. because the canister was upgraded before the result was stored.
+async fn record_existing_lease(
+    calling_principal: Principal,
+    bid_id: BidId,
+    deployment_id: DeploymentId,
+    selection_reason: Option<String>,
+) -> Result<(String, String), ApiError> {
+    let order = (bid_id.gseq, bid_id.oseq);
+    let provider_url = AkashService::default()
+        .provider_url(bid_id.provider)
+        .await
+        .map_err(|e| ApiError::internal(&format!("Error fetching provider: {}", e)))?;
+
+    log_warn!(
+        format!(
+            "[Deployment {}]: Lease for order {}/{} already created on Akash",
+            deployment_id, order.0, order.1
+        ),
+        "record_existing_lease"
+    );
+
+    // the hash of the transaction that created the lease has been lost
+    set_lease_created(
+        calling_principal,
+        deployment_id,
+        order,
+        String::new(),
+        provider_url,
+        selection_reason,
+    )
+}
+
+fn set_lease_created(
+    calling_principal: Principal,
+    deployment_id: DeploymentId,
+    order: OrderSeq,
+    tx_hash: String,
+    provider_url: String,
+    selection_reason: Option<String>,
+) -> Result<(String, String), ApiError> {
     let deployment_update = DeploymentState::LeaseCreated {
         tx_hash: tx_hash.clone(),
         provider_url: provider_url.clone(),
+        order: Some(order),
+        selection_reason,
     };
-    deployment_service.update_deployment_state(
+    DeploymentsService::default().update_deployment_state(
         calling_principal,
         deployment_id,
         deployment_update,
@@ -658,7 +1468,10 @@ async fn handle_create_lease(
     )?;
 
     log_info!(
-        format!("[Deployment {}]: Lease created", deployment_id),
+        format!(
+            "[Deployment {}]: Lease created for order {}/{}",
+            deployment_id, order.0, order.1
+        ),
         "handle_create_lease"
     );
 
@@ -723,22 +1536,101 @@ async fn try_close_akash_deployment(deployment_id: &DeploymentId) -> Result<(),
         "try_close_akash_deployment"
     );
 
-    let dseq = DeploymentsService::default()
-        .get_akash_deployment_info(deployment_id)?
-        .ok_or(ApiError::not_found(&format!(
-            "Deployment with id {} is initialized but has not been created",
-            deployment_id
-        )))?;
+    let deployments_service = DeploymentsService::default();
+    let deployment = deployments_service.get_deployment(deployment_id)?;
+    let akash_service = AkashService::default().with_account(deployment.akash_account());
+
+    let dseq = match deployment.get_akash_info() {
+        Some(dseq) => dseq,
+        // the creation may have been broadcast without being confirmed
+        None => {
+            let dseq = deployment
+                .reserved_dseq()
+                .ok_or(ApiError::not_found(&format!(
+                    "Deployment with id {} is initialized but has not been created",
+                    deployment_id
+                )))?;
+
+            let exists = akash_service
+                .deployment_exists(dseq)
+                .await
+                .map_err(|e| ApiError::internal(&format!("Error fetching deployment: {}", e)))?;
+            if !exists {
+                return await_deployment_creation(&akash_service, deployment_id, dseq).await;
+            }
+
+            dseq
+        }
+    };
+
+    // the escrow is snapshotted before closing, to know how much is refunded by the closure
+    let escrow_before_close = match akash_service.deployment_escrow(dseq).await {
+        Ok((_, escrow)) => escrow,
+        Err(e) => {
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Failed to fetch escrow account before closing, will retry: {}",
+                    deployment_id, e
+                ),
+                "try_close_akash_deployment"
+            );
+            None
+        }
+    };
 
-    AkashService::default()
-        .close_deployment(dseq)
+    let close_result = akash_service.close_deployment(*deployment_id, dseq).await;
+
+    match &close_result {
+        Ok(tx_hash) => log_info!(
+            format!(
+                "[Deployment {}]: Sent close transaction {} for Akash deployment",
+                deployment_id, tx_hash
+            ),
+            "try_close_akash_deployment"
+        ),
+        Err(e) => log_warn!(
+            format!(
+                "[Deployment {}]: Failed to close Akash deployment, will retry: {}",
+                deployment_id, e
+            ),
+            "try_close_akash_deployment"
+        ),
+    }
+
+    // the close is confirmed on chain (and retried if needed) by the pending closes job
+    PendingClosesService::default().enqueue(
+        *deployment_id,
+        dseq,
+        escrow_before_close,
+        close_result,
+    );
+
+    Ok(())
+}
+
+/// Queues the close of a deployment whose creation transaction may still be applied,
+/// so that the deployment is closed if it appears on chain before the transaction expires.
+async fn await_deployment_creation(
+    akash_service: &AkashService,
+    deployment_id: &DeploymentId,
+    dseq: u64,
+) -> Result<(), ApiError> {
+    // the creation transaction was signed before now, so it expires at the latest at this height
+    let until_height = akash_service
+        .latest_block_height()
         .await
-        .map_err(|e| ApiError::internal(&format!("Error closing Akash deployment: {}", e)))?;
+        .map_err(|e| ApiError::internal(&format!("Error fetching block height: {}", e)))?
+        + akash_service.get_config().akash_config().tx_timeout_blocks;
 
     log_info!(
-        format!("[Deployment {}]: Closed Akash deployment", deployment_id),
-        "try_close_akash_deployment"
+        format!(
+            "[Deployment {}]: Deployment with dseq {} not on chain, waiting for its creation until height {}",
+            deployment_id, dseq, until_height
+        ),
+        "await_deployment_creation"
     );
 
+    PendingClosesService::default().enqueue_awaiting_creation(*deployment_id, dseq, until_height);
+
     Ok(())
 }
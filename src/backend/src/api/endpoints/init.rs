@@ -1,14 +1,18 @@
 use crate::{
     api::{
-        log_info, AkashConfig, ApiError, Config, ConfigService, User, UserId, UserRole,
-        UsersService,
+        log_info, AkashConfig, ApiError, Config, ConfigService, FeeConfig, PricingConfig, User,
+        UserId, UserRole, UsersService, DEFAULT_TX_TIMEOUT_BLOCKS,
     },
     helpers::EcdsaKeyIds,
 };
 use candid::Principal;
 use ic_cdk::*;
 
-use super::websocket::init_ic_websocket;
+use super::{
+    akash::init_canister_account_cache, auto_top_up::init_auto_top_up_job,
+    deployment::resume_deployments, pending_closes::init_pending_closes_job,
+    reconciliation::init_reconciliation_job, websocket::init_ic_websocket,
+};
 
 #[init]
 fn init(is_mainnet: bool) {
@@ -23,6 +27,15 @@ fn init(is_mainnet: bool) {
 
     init_ic_websocket();
 
+    init_canister_account_cache();
+
+    // timers do not survive upgrades, so the jobs are also started in `post_upgrade`
+    init_pending_closes_job();
+
+    init_reconciliation_job();
+
+    init_auto_top_up_job();
+
     log_info!(
         format!("Successfully initialized. Is mainnet: {is_mainnet}"),
         "init"
@@ -35,6 +48,16 @@ fn post_upgrade(is_mainnet: bool) {
 
     init_ic_websocket();
 
+    init_canister_account_cache();
+
+    init_pending_closes_job();
+
+    init_reconciliation_job();
+
+    init_auto_top_up_job();
+
+    resume_deployments();
+
     log_info!(
         format!("Successfully upgraded. Is mainnet: {is_mainnet}"),
         "post_upgrade"
@@ -56,6 +79,10 @@ impl Init {
                 AkashConfig {
                     // fetched from https://api.akashnet.net/cosmos/params/v1beta1/params?subspace=deployment&key=MinDeposits
                     min_deposit_uakt_amount: 500_000,
+                    pricing: PricingConfig::default(),
+                    fees: FeeConfig::default(),
+                    tx_timeout_blocks: DEFAULT_TX_TIMEOUT_BLOCKS,
+                    per_user_accounts: false,
                 },
             )
         } else {
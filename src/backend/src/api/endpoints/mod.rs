@@ -1,7 +1,11 @@
 mod akash;
+mod auto_top_up;
+mod config;
 mod deployment;
 mod init;
 mod ledger;
 mod logs;
+mod pending_closes;
+mod reconciliation;
 mod users;
 mod websocket;
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use candid::Principal;
+use ic_cdk::{caller, query};
+use utils::get_time_nanos;
+
+use crate::{
+    api::{
+        log_error, log_info, log_warn, map_pending_close, AccessControlService, AkashService,
+        AktLedgerEntryKind, ApiError, ApiResult, DeploymentId, DeploymentsService, EscrowSnapshot,
+        MappedPendingClose, PendingClose, PendingClosesService, UsersService,
+    },
+    helpers::uakt_to_akt,
+};
+
+const PENDING_CLOSES_JOB_INTERVAL_SECS: u64 = 30;
+
+#[query]
+fn list_pending_closes() -> ApiResult<Vec<MappedPendingClose>> {
+    let calling_principal = caller();
+
+    PendingClosesEndpoints::default()
+        .list_pending_closes(calling_principal)
+        .map(|pending_closes| {
+            pending_closes
+                .into_iter()
+                .map(|(deployment_id, pending_close)| {
+                    map_pending_close(deployment_id, pending_close)
+                })
+                .collect()
+        })
+        .into()
+}
+
+#[derive(Default)]
+struct PendingClosesEndpoints {
+    access_control_service: AccessControlService,
+    pending_closes_service: PendingClosesService,
+}
+
+impl PendingClosesEndpoints {
+    fn list_pending_closes(
+        &self,
+        calling_principal: Principal,
+    ) -> Result<Vec<(DeploymentId, PendingClose)>, ApiError> {
+        self.access_control_service
+            .assert_principal_is_admin(&calling_principal)?;
+
+        Ok(self.pending_closes_service.get_pending_closes())
+    }
+}
+
+/// Starts the job that confirms the pending closes on chain and retries the failed ones.
+pub fn init_pending_closes_job() {
+    ic_cdk_timers::set_timer_interval(
+        Duration::from_secs(PENDING_CLOSES_JOB_INTERVAL_SECS),
+        || ic_cdk::spawn(process_pending_closes()),
+    );
+}
+
+async fn process_pending_closes() {
+    let mut pending_closes_service = PendingClosesService::default();
+
+    for (deployment_id, mut pending_close) in pending_closes_service.get_due_pending_closes() {
+        // postpone the close before awaiting, so that the next run of the job
+        // does not process it again while this one is still in progress
+        pending_close.postpone(get_time_nanos());
+        pending_closes_service.update_pending_close(deployment_id, pending_close.clone());
+
+        process_pending_close(&mut pending_closes_service, deployment_id, pending_close).await;
+    }
+}
+
+async fn process_pending_close(
+    pending_closes_service: &mut PendingClosesService,
+    deployment_id: DeploymentId,
+    mut pending_close: PendingClose,
+) {
+    let akash_account = match DeploymentsService::default().get_akash_account(&deployment_id) {
+        Ok(akash_account) => akash_account,
+        Err(e) => {
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Failed to get the Akash account of the deployment: {:?}",
+                    deployment_id, e
+                ),
+                "process_pending_close"
+            );
+            return;
+        }
+    };
+    let akash_service = AkashService::default()
+        .with_timer_polling()
+        .with_account(akash_account);
+
+    if let Some(until_height) = pending_close.awaiting_creation_until {
+        match check_deployment_creation(&akash_service, pending_close.dseq, until_height).await {
+            Ok(CreationOutcome::Created) => {
+                log_warn!(
+                    format!(
+                        "[Deployment {}]: Deployment with dseq {} created on chain after failing, closing it",
+                        deployment_id, pending_close.dseq
+                    ),
+                    "process_pending_close"
+                );
+                pending_close.awaiting_creation_until = None;
+            }
+            Ok(CreationOutcome::Expired) => {
+                pending_closes_service.remove_pending_close(&deployment_id);
+
+                log_info!(
+                    format!(
+                        "[Deployment {}]: Deployment with dseq {} never created on chain, nothing to close",
+                        deployment_id, pending_close.dseq
+                    ),
+                    "process_pending_close"
+                );
+                return;
+            }
+            Ok(CreationOutcome::Pending) => return,
+            Err(e) => {
+                log_warn!(
+                    format!(
+                        "[Deployment {}]: Failed to check the creation of the deployment: {}",
+                        deployment_id, e
+                    ),
+                    "process_pending_close"
+                );
+                return;
+            }
+        }
+    }
+
+    match akash_service.deployment_escrow(pending_close.dseq).await {
+        Ok((true, escrow_after_close)) => {
+            log_info!(
+                format!(
+                    "[Deployment {}]: Close confirmed on chain after {} attempts",
+                    deployment_id, pending_close.attempts
+                ),
+                "process_pending_close"
+            );
+
+            match refund_escrow(deployment_id, &pending_close, escrow_after_close) {
+                Ok(()) => {
+                    pending_closes_service.remove_pending_close(&deployment_id);
+                }
+                Err(e) => {
+                    log_error!(
+                        format!("[Deployment {}]: {}", deployment_id, e),
+                        "process_pending_close"
+                    );
+
+                    pending_close.record_refund_owed(e, get_time_nanos());
+                    pending_closes_service.update_pending_close(deployment_id, pending_close);
+                }
+            }
+            return;
+        }
+        Ok((false, escrow)) => {
+            // the deployment is still open, so the snapshot missed before the first close
+            // can still be taken
+            if pending_close.escrow_before_close.is_none() {
+                pending_close.escrow_before_close = escrow;
+            }
+        }
+        Err(e) => {
+            // the deployment may still be closed, the close is retried anyway
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Failed to query deployment on chain: {}",
+                    deployment_id, e
+                ),
+                "process_pending_close"
+            );
+        }
+    }
+
+    let was_stuck = pending_close.is_stuck();
+
+    let close_result = akash_service
+        .close_deployment(deployment_id, pending_close.dseq)
+        .await;
+    if let Err(e) = &close_result {
+        log_warn!(
+            format!(
+                "[Deployment {}]: Failed to close Akash deployment (attempt {}): {}",
+                deployment_id,
+                pending_close.attempts + 1,
+                e
+            ),
+            "process_pending_close"
+        );
+    }
+
+    pending_close.record_attempt(close_result, get_time_nanos());
+
+    if pending_close.is_stuck() && !was_stuck {
+        log_error!(
+            format!(
+                "[Deployment {}]: Akash deployment {} is still not closed after {} attempts",
+                deployment_id, pending_close.dseq, pending_close.attempts
+            ),
+            "process_pending_close"
+        );
+    }
+
+    pending_closes_service.update_pending_close(deployment_id, pending_close);
+}
+
+enum CreationOutcome {
+    Created,
+    /// the chain is past the height up to which the creation could be applied
+    Expired,
+    Pending,
+}
+
+async fn check_deployment_creation(
+    akash_service: &AkashService,
+    dseq: u64,
+    until_height: u64,
+) -> Result<CreationOutcome, String> {
+    // the height is fetched first, so that a deployment still missing afterwards
+    // can no longer be created once the height is past the bound
+    let height = akash_service.latest_block_height().await?;
+
+    if akash_service.deployment_exists(dseq).await? {
+        Ok(CreationOutcome::Created)
+    } else if height > until_height {
+        Ok(CreationOutcome::Expired)
+    } else {
+        Ok(CreationOutcome::Pending)
+    }
+}
+
+/// Adds back to the user's balance the AKT returned by the escrow of the closed deployment.
+///
+/// Returns an error if the refund could not be credited, in which case it is owed to the user.
+fn refund_escrow(
+    deployment_id: DeploymentId,
+    pending_close: &PendingClose,
+    escrow_after_close: Option<EscrowSnapshot>,
+) -> Result<(), String> {
+    let (Some(escrow_before_close), Some(escrow_after_close)) =
+        (&pending_close.escrow_before_close, escrow_after_close)
+    else {
+        return Err(String::from(
+            "Escrow account not available, the refund must be computed manually",
+        ));
+    };
+
+    // bank balances are integers, fractions of uakt are not returned
+    let refunded_uakt = escrow_before_close
+        .refunded_uakt(&escrow_after_close)
+        .floor() as u64;
+    if refunded_uakt == 0 {
+        return Ok(());
+    }
+
+    let updated_balance = DeploymentsService::default()
+        .get_deployment(&deployment_id)
+        .and_then(|deployment| {
+            UsersService::default().credit_user(
+                deployment.user_id(),
+                uakt_to_akt(refunded_uakt),
+                AktLedgerEntryKind::EscrowRefund {
+                    deployment_id: deployment_id.to_string(),
+                },
+            )
+        })
+        .map_err(|e| {
+            format!(
+                "Failed to refund {} uakt from escrow: {:?}",
+                refunded_uakt, e
+            )
+        })?;
+
+    log_info!(
+        format!(
+            "[Deployment {}]: Refunded {} uakt from escrow. Current user balance: {} AKT",
+            deployment_id, refunded_uakt, updated_balance
+        ),
+        "refund_escrow"
+    );
+
+    Ok(())
+}
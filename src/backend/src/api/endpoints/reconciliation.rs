@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use crate::{
+    akash::proto::{
+        deployment::{query::QueryDeploymentResponse, DeploymentState as AkashDeploymentState},
+        escrow::types::State as EscrowAccountState,
+        market::lease::{Lease, State as LeaseState},
+    },
+    api::{
+        log_info, log_warn, AkashService, ApiError, Deployment, DeploymentId, DeploymentState,
+        DeploymentsService,
+    },
+};
+
+const RECONCILIATION_JOB_INTERVAL_SECS: u64 = 600;
+
+/// Starts the job that checks the leased deployments against their state on Akash.
+pub fn init_reconciliation_job() {
+    ic_cdk_timers::set_timer_interval(
+        Duration::from_secs(RECONCILIATION_JOB_INTERVAL_SECS),
+        || ic_cdk::spawn(reconcile_deployments()),
+    );
+}
+
+async fn reconcile_deployments() {
+    for (deployment_id, deployment) in DeploymentsService::default().get_deployments_to_reconcile()
+    {
+        if let Err(e) = reconcile_deployment(deployment_id, deployment).await {
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Failed to reconcile with the chain: {:?}",
+                    deployment_id, e
+                ),
+                "reconcile_deployments"
+            );
+        }
+    }
+}
+
+async fn reconcile_deployment(
+    deployment_id: DeploymentId,
+    deployment: Deployment,
+) -> Result<(), ApiError> {
+    let Some(dseq) = deployment.get_akash_info() else {
+        return Ok(());
+    };
+
+    let akash_service = AkashService::default().with_account(deployment.akash_account());
+    let on_chain = akash_service
+        .deployment(dseq)
+        .await
+        .map_err(|e| ApiError::internal(&format!("Error fetching deployment: {}", e)))?;
+    let leases = akash_service
+        .leases(dseq)
+        .await
+        .map_err(|e| ApiError::internal(&format!("Error fetching leases: {}", e)))?;
+
+    let updates = derive_states(&deployment, on_chain.as_ref(), &leases);
+
+    if updates.contains(&DeploymentState::Closed) {
+        log_warn!(
+            format!(
+                "[Deployment {}]: Akash deployment {} has been closed outside of the canister",
+                deployment_id, dseq
+            ),
+            "reconcile_deployment"
+        );
+    }
+
+    // the owner is notified of every state derived from the chain,
+    // a closed deployment is terminal and is not reconciled anymore
+    let owner = deployment.user_id().principal();
+    for update in updates {
+        log_info!(
+            format!(
+                "[Deployment {}]: Reconciled with the chain: {}",
+                deployment_id,
+                update.name()
+            ),
+            "reconcile_deployment"
+        );
+
+        DeploymentsService::default().update_deployment_state(
+            owner,
+            deployment_id,
+            update,
+            true,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Derives the states the deployment went through on chain without the canister knowing it.
+fn derive_states(
+    deployment: &Deployment,
+    on_chain: Option<&QueryDeploymentResponse>,
+    leases: &[Lease],
+) -> Vec<DeploymentState> {
+    let reported_orders = deployment
+        .get_history()
+        .into_iter()
+        .filter_map(|(_, state)| match state {
+            DeploymentState::LeaseClosedByProvider { order, .. } => Some(order),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let leased_orders = deployment.leased_orders();
+
+    let mut updates = leases
+        .iter()
+        .filter(|lease| lease.state == LeaseState::Closed as i32)
+        .filter_map(|lease| lease.lease_id.as_ref())
+        .filter(|lease_id| {
+            let order = (lease_id.gseq, lease_id.oseq);
+            leased_orders.contains(&order) && !reported_orders.contains(&order)
+        })
+        .map(|lease_id| DeploymentState::LeaseClosedByProvider {
+            order: (lease_id.gseq, lease_id.oseq),
+            provider: lease_id.provider.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    // Akash closes the leases with insufficient funds once the escrow is overdrawn
+    let escrow_overdrawn = on_chain
+        .and_then(|res| res.escrow_account.as_ref())
+        .is_some_and(|account| account.state == EscrowAccountState::AccountOverdrawn as i32)
+        || leases
+            .iter()
+            .any(|lease| lease.state == LeaseState::InsufficientFunds as i32);
+    if escrow_overdrawn {
+        updates.push(DeploymentState::EscrowOverdrawn);
+    }
+
+    // a deployment missing on chain has been closed and pruned
+    if on_chain.map_or(true, is_closed) {
+        updates.push(DeploymentState::Closed);
+    }
+
+    updates
+}
+
+fn is_closed(on_chain: &QueryDeploymentResponse) -> bool {
+    on_chain.deployment.as_ref().map_or(true, |deployment| {
+        deployment.state == AkashDeploymentState::Closed as i32
+    })
+}
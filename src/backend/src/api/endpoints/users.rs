@@ -2,8 +2,8 @@ use candid::Principal;
 use ic_cdk::*;
 
 use crate::api::{
-    AccessControlService, ApiError, ApiResult, LedgerService, LogService, User, UserId, UserRole,
-    UsersService,
+    AccessControlService, AktLedgerEntry, ApiError, ApiResult, LedgerService, LogService, User,
+    UserId, UserRole, UsersService,
 };
 
 #[query]
@@ -24,6 +24,24 @@ fn get_my_user() -> ApiResult<User> {
         .into()
 }
 
+#[query]
+fn get_my_akt_ledger_entries() -> ApiResult<Vec<AktLedgerEntry>> {
+    let calling_principal = caller();
+
+    UsersEndpoints::default()
+        .get_my_akt_ledger_entries(calling_principal)
+        .into()
+}
+
+#[query]
+fn list_akt_ledger_entries() -> ApiResult<Vec<AktLedgerEntry>> {
+    let calling_principal = caller();
+
+    UsersEndpoints::default()
+        .list_akt_ledger_entries(calling_principal)
+        .into()
+}
+
 #[update]
 fn create_user() -> ApiResult<Principal> {
     let calling_principal = caller();
@@ -80,6 +98,28 @@ impl UsersEndpoints {
         self.users_service.get_user(&calling_principal.into())
     }
 
+    fn get_my_akt_ledger_entries(
+        &self,
+        calling_principal: Principal,
+    ) -> Result<Vec<AktLedgerEntry>, ApiError> {
+        self.access_control_service
+            .assert_principal_is_user(&calling_principal)?;
+
+        Ok(self
+            .users_service
+            .get_akt_ledger_entries(Some(&calling_principal.into())))
+    }
+
+    fn list_akt_ledger_entries(
+        &self,
+        calling_principal: Principal,
+    ) -> Result<Vec<AktLedgerEntry>, ApiError> {
+        self.access_control_service
+            .assert_principal_is_admin(&calling_principal)?;
+
+        Ok(self.users_service.get_akt_ledger_entries(None))
+    }
+
     fn create_user(&mut self, calling_principal: Principal) -> Result<UserId, ApiError> {
         self.access_control_service
             .assert_principal_not_anonymous(&calling_principal)?;
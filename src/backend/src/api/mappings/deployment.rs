@@ -1,22 +1,33 @@
 use candid::{CandidType, Deserialize, Principal};
 
-use crate::api::{Deployment, DeploymentParams, DeploymentState, TimestampNs};
+use crate::api::{
+    AutoTopUp, BidSelectionStrategy, Deployment, DeploymentParams, DeploymentRevision,
+    DeploymentState, PendingDeposit, TimestampNs,
+};
 
 #[derive(Debug, CandidType, Deserialize, Clone)]
 pub struct MappedDeployment {
-    params: DeploymentParams,
+    params: Option<DeploymentParams>,
+    sdl: Option<String>,
     user_id: Principal,
     state_history: Vec<(TimestampNs, DeploymentState)>,
     icp_price: f64,
+    bid_selection: BidSelectionStrategy,
+    auto_top_up: Option<AutoTopUp>,
+    pending_deposits: Vec<PendingDeposit>,
 }
 
 impl From<Deployment> for MappedDeployment {
     fn from(deployment: Deployment) -> Self {
         Self {
             params: deployment.params(),
+            sdl: deployment.sdl(),
             user_id: deployment.user_id().principal(),
             state_history: deployment.get_history(),
             icp_price: deployment.icp_price(),
+            bid_selection: deployment.bid_selection(),
+            auto_top_up: deployment.auto_top_up(),
+            pending_deposits: deployment.pending_deposits(),
         }
     }
 }
@@ -33,3 +44,27 @@ pub fn map_deployment(deployment_id: String, deployment: Deployment) -> GetDeplo
         deployment: deployment.into(),
     }
 }
+
+#[derive(Debug, CandidType, Deserialize, Clone)]
+pub struct GetDeploymentManifestResponse {
+    revision: u32,
+    created_at: TimestampNs,
+    tx_hash: Option<String>,
+    sdl: String,
+    manifest_sorted_json: String,
+    manifest_version: String,
+}
+
+pub fn map_deployment_revision(
+    revision: u32,
+    deployment_revision: DeploymentRevision,
+) -> GetDeploymentManifestResponse {
+    GetDeploymentManifestResponse {
+        revision,
+        created_at: deployment_revision.created_at,
+        tx_hash: deployment_revision.tx_hash,
+        sdl: deployment_revision.sdl,
+        manifest_sorted_json: deployment_revision.manifest_sorted_json,
+        manifest_version: deployment_revision.manifest_version,
+    }
+}
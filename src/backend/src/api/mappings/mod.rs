@@ -1,5 +1,7 @@
 mod deployment;
 mod log;
+mod pending_close;
 
 pub(super) use deployment::*;
 pub(super) use log::*;
+pub(super) use pending_close::*;
@@ -0,0 +1,39 @@
+use candid::{CandidType, Deserialize};
+
+use crate::api::{DeploymentId, EscrowSnapshot, PendingClose, TimestampNs};
+
+#[derive(Debug, CandidType, Deserialize, Clone)]
+pub struct MappedPendingClose {
+    deployment_id: String,
+    dseq: u64,
+    enqueued_at: TimestampNs,
+    attempts: u32,
+    next_attempt_at: TimestampNs,
+    last_tx_hash: Option<String>,
+    last_error: Option<String>,
+    /// the close keeps failing and needs to be looked into
+    stuck: bool,
+    /// the deployment is closed, but its escrow refund has to be credited manually
+    refund_owed_since: Option<TimestampNs>,
+    escrow_before_close: Option<EscrowSnapshot>,
+    awaiting_creation_until: Option<u64>,
+}
+
+pub fn map_pending_close(
+    deployment_id: DeploymentId,
+    pending_close: PendingClose,
+) -> MappedPendingClose {
+    MappedPendingClose {
+        deployment_id: deployment_id.to_string(),
+        dseq: pending_close.dseq,
+        enqueued_at: pending_close.enqueued_at,
+        attempts: pending_close.attempts,
+        next_attempt_at: pending_close.next_attempt_at,
+        stuck: pending_close.is_stuck(),
+        last_tx_hash: pending_close.last_tx_hash,
+        last_error: pending_close.last_error,
+        refund_owed_since: pending_close.refund_owed_since,
+        escrow_before_close: pending_close.escrow_before_close,
+        awaiting_creation_until: pending_close.awaiting_creation_until,
+    }
+}
@@ -0,0 +1,14 @@
+use std::{cell::RefCell, collections::BTreeSet};
+
+use crate::api::{DeploymentId, OrderSeq};
+
+thread_local! {
+    /// orders whose bids are being accepted, to avoid accepting two bids on the same order concurrently
+    /* flexible */ static STATE: RefCell<BTreeSet<(DeploymentId, OrderSeq)>> = RefCell::new(BTreeSet::default());
+}
+
+pub fn accepting_orders_state_mut<R>(
+    f: impl FnOnce(&mut BTreeSet<(DeploymentId, OrderSeq)>) -> R,
+) -> R {
+    STATE.with_borrow_mut(|s| f(s))
+}
@@ -0,0 +1,15 @@
+use std::cell::RefCell;
+
+use crate::api::AccountSequenceState;
+
+thread_local! {
+    /* flexible */ static STATE: RefCell<AccountSequenceState> = RefCell::new(AccountSequenceState::default());
+}
+
+pub fn account_sequence_state<R>(f: impl FnOnce(&AccountSequenceState) -> R) -> R {
+    STATE.with_borrow(|s| f(s))
+}
+
+pub fn account_sequence_state_mut<R>(f: impl FnOnce(&mut AccountSequenceState) -> R) -> R {
+    STATE.with_borrow_mut(|s| f(s))
+}
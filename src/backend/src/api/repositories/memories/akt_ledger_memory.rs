@@ -0,0 +1,17 @@
+use super::{Memory, AKT_LEDGER_INDEX_MEMORY_ID, AKT_LEDGER_MEMORY_ID, MEMORY_MANAGER};
+use crate::api::AktLedgerEntry;
+use ic_stable_structures::Log;
+
+pub type AktLedgerMemory = Log<AktLedgerEntry, Memory, Memory>;
+
+pub fn init_akt_ledger() -> AktLedgerMemory {
+    AktLedgerMemory::init(get_akt_ledger_index_memory(), get_akt_ledger_memory()).unwrap()
+}
+
+fn get_akt_ledger_index_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(AKT_LEDGER_INDEX_MEMORY_ID))
+}
+
+fn get_akt_ledger_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(AKT_LEDGER_MEMORY_ID))
+}
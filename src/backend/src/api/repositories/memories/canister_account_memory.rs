@@ -0,0 +1,87 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+use ic_stable_structures::Cell;
+
+use crate::{
+    api::{AkashAccount, CanisterAccount, CanisterAccountCache, UserId},
+    helpers::EcdsaKeyIds,
+};
+
+use super::{Memory, CANISTER_ACCOUNT_MEMORY_ID, MEMORY_MANAGER};
+
+pub type CanisterAccountMemory = Cell<CanisterAccountCache, Memory>;
+
+thread_local! {
+    /// copy of the stable memory, to avoid decoding it for each transaction
+    /* flexible */ static CANISTER_ACCOUNT: RefCell<Option<CanisterAccount>> = RefCell::new(init_canister_account().get().0.clone());
+    /// accounts derived for the users, only kept in the heap as they are fetched again cheaply after an upgrade
+    /* flexible */ static USER_ACCOUNTS: RefCell<BTreeMap<UserId, CanisterAccount>> = RefCell::new(BTreeMap::default());
+}
+
+pub fn init_canister_account() -> CanisterAccountMemory {
+    CanisterAccountMemory::init(
+        get_canister_account_memory(),
+        CanisterAccountCache::default(),
+    )
+    .unwrap()
+}
+
+/// The cached account, `None` if it has not been fetched yet for the given key.
+pub fn cached_canister_account(
+    akash_account: &AkashAccount,
+    ecdsa_key: &EcdsaKeyIds,
+) -> Option<CanisterAccount> {
+    match akash_account {
+        AkashAccount::Canister => CANISTER_ACCOUNT.with_borrow(|account| account.clone()),
+        AkashAccount::User(user_id) => {
+            USER_ACCOUNTS.with_borrow(|accounts| accounts.get(user_id).cloned())
+        }
+    }
+    .filter(|account| account.is_for(ecdsa_key))
+}
+
+/// Caches the account of the canister in heap and stable memory, and the accounts of the users in heap memory.
+pub fn set_cached_canister_account(
+    akash_account: &AkashAccount,
+    account: CanisterAccount,
+) -> Result<(), String> {
+    match akash_account {
+        AkashAccount::Canister => store_canister_account(Some(account)),
+        AkashAccount::User(user_id) => {
+            USER_ACCOUNTS.with_borrow_mut(|accounts| accounts.insert(*user_id, account));
+            Ok(())
+        }
+    }
+}
+
+/// Clears the cached accounts if they have been fetched for a different key than the given one.
+pub fn invalidate_canister_account(ecdsa_key: &EcdsaKeyIds) -> Result<(), String> {
+    USER_ACCOUNTS
+        .with_borrow_mut(|accounts| accounts.retain(|_, account| account.is_for(ecdsa_key)));
+
+    let is_stale = CANISTER_ACCOUNT.with_borrow(|account| {
+        account
+            .as_ref()
+            .is_some_and(|account| !account.is_for(ecdsa_key))
+    });
+
+    if is_stale {
+        store_canister_account(None)?;
+    }
+
+    Ok(())
+}
+
+fn store_canister_account(account: Option<CanisterAccount>) -> Result<(), String> {
+    init_canister_account()
+        .set(CanisterAccountCache(account.clone()))
+        .map_err(|e| format!("could not store canister account: {:?}", e))?;
+
+    CANISTER_ACCOUNT.with_borrow_mut(|cached| *cached = account);
+
+    Ok(())
+}
+
+fn get_canister_account_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(CANISTER_ACCOUNT_MEMORY_ID))
+}
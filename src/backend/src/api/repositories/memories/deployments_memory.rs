@@ -7,6 +7,7 @@ use super::{Memory, DEPLOYMENTS_MEMORY_ID, MEMORY_MANAGER};
 
 pub type DeploymentsMemory = BTreeMap<DeploymentId, Deployment, Memory>;
 
+/// last dseq reserved for a deployment
 pub type DeploymentsCounterMemory = Cell<u64, Memory>;
 
 pub fn init_deployments() -> DeploymentsMemory {
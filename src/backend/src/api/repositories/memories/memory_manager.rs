@@ -15,3 +15,9 @@ pub(super) const DEPLOYMENTS_MEMORY_ID: MemoryId = MemoryId::new(1);
 pub(super) const LOGS_INDEX_MEMORY_ID: MemoryId = MemoryId::new(2);
 pub(super) const LOGS_MEMORY_ID: MemoryId = MemoryId::new(3);
 pub(super) const DEPLOYMENTS_COUNTER_MEMORY_ID: MemoryId = MemoryId::new(4);
+pub(super) const PENDING_CLOSES_MEMORY_ID: MemoryId = MemoryId::new(5);
+pub(super) const AKT_LEDGER_INDEX_MEMORY_ID: MemoryId = MemoryId::new(6);
+pub(super) const AKT_LEDGER_MEMORY_ID: MemoryId = MemoryId::new(7);
+pub(super) const RESOURCE_TIERS_MEMORY_ID: MemoryId = MemoryId::new(8);
+pub(super) const CANISTER_ACCOUNT_MEMORY_ID: MemoryId = MemoryId::new(9);
+pub(super) const PER_USER_ACCOUNTS_MEMORY_ID: MemoryId = MemoryId::new(10);
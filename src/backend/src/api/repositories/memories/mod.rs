@@ -1,12 +1,26 @@
+mod accepting_orders_state;
+mod account_sequence_state;
+mod akt_ledger_memory;
+mod canister_account_memory;
 mod config_state;
 mod deployments_memory;
 mod log_memory;
 mod memory_manager;
+mod pending_closes_memory;
+mod per_user_accounts_memory;
+mod resource_tiers_memory;
 mod users_memory;
 
 use memory_manager::*;
 
+pub use accepting_orders_state::*;
+pub use account_sequence_state::*;
+pub use akt_ledger_memory::*;
+pub use canister_account_memory::*;
 pub use config_state::*;
 pub use deployments_memory::*;
 pub(super) use log_memory::*;
+pub use pending_closes_memory::*;
+pub use per_user_accounts_memory::*;
+pub use resource_tiers_memory::*;
 pub use users_memory::*;
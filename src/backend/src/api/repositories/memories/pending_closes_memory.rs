@@ -0,0 +1,15 @@
+use ic_stable_structures::BTreeMap;
+
+use crate::api::{DeploymentId, PendingClose};
+
+use super::{Memory, MEMORY_MANAGER, PENDING_CLOSES_MEMORY_ID};
+
+pub type PendingClosesMemory = BTreeMap<DeploymentId, PendingClose, Memory>;
+
+pub fn init_pending_closes() -> PendingClosesMemory {
+    PendingClosesMemory::init(get_pending_closes_memory())
+}
+
+fn get_pending_closes_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(PENDING_CLOSES_MEMORY_ID))
+}
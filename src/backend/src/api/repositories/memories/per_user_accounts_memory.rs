@@ -0,0 +1,14 @@
+use ic_stable_structures::Cell;
+
+use super::{Memory, MEMORY_MANAGER, PER_USER_ACCOUNTS_MEMORY_ID};
+
+/// 1 if the per-user accounts are enabled, `bool` not being [ic_stable_structures::Storable]
+pub type PerUserAccountsMemory = Cell<u8, Memory>;
+
+pub fn init_per_user_accounts() -> PerUserAccountsMemory {
+    PerUserAccountsMemory::init(get_per_user_accounts_memory(), 0).unwrap()
+}
+
+fn get_per_user_accounts_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(PER_USER_ACCOUNTS_MEMORY_ID))
+}
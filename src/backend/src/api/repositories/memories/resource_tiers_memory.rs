@@ -0,0 +1,15 @@
+use ic_stable_structures::Cell;
+
+use crate::api::ResourceTiers;
+
+use super::{Memory, MEMORY_MANAGER, RESOURCE_TIERS_MEMORY_ID};
+
+pub type ResourceTiersMemory = Cell<ResourceTiers, Memory>;
+
+pub fn init_resource_tiers() -> ResourceTiersMemory {
+    ResourceTiersMemory::init(get_resource_tiers_memory(), ResourceTiers::default()).unwrap()
+}
+
+fn get_resource_tiers_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(RESOURCE_TIERS_MEMORY_ID))
+}
@@ -1,59 +1,286 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr, time::Duration};
 
-use cosmrs::AccountId;
+use cosmrs::{auth::BaseAccount, crypto::PublicKey, AccountId};
+use ic_cdk::api::management_canister::main::raw_rand;
+use ic_tendermint_rpc::{ExecTxResult, TxPolling, TxStatus};
 
-use utils::base64_decode;
+use utils::{base64_decode, get_time_nanos};
 
-use crate::api::repositories::{init_deployments_counter, DeploymentsCounterMemory};
+use crate::api::repositories::{
+    account_sequence_state, account_sequence_state_mut, cached_canister_account,
+    init_deployments_counter, set_cached_canister_account,
+};
 use crate::{
     akash::{
         address::get_account_id_from_public_key,
         auth::get_account,
-        bank::{create_send_tx, get_balance},
-        bids::fetch_bids,
-        certificate::create_certificate_tx,
+        bank::{create_send_msg, get_balance},
+        bids::{fetch_bid, fetch_bids, ACTIVE_BID_STATE},
+        certificate::create_certificate_msg,
         deployment::{
-            close_deployment_tx, create_deployment_tx, deposit_deployment_tx,
-            update_deployment_sdl_tx,
+            close_deployment_msg, create_deployment_msg, deposit_deployment_msg, escrow_snapshot,
+            fetch_deployment, update_deployment_sdl_msg,
+        },
+        events::{created_deployment_id, created_lease_ids},
+        lease::{create_lease_msg, fetch_lease, fetch_leases},
+        proto::{
+            deployment::{query::QueryDeploymentResponse, DeploymentState},
+            market::{
+                bid::{Bid, BidId},
+                lease::{Lease, LeaseId},
+                query::QueryLeaseResponse,
+            },
         },
-        lease::create_lease_tx,
         provider::fetch_provider,
         sdl::SdlV3,
+        tx::{create_tx, TxConfig, TxMemo, TxMessage},
+    },
+    api::{
+        config_state, log_info, log_warn, AkashAccount, CanisterAccount, Config, DeploymentId,
+        DeploymentLease, DeploymentLeaseStatus, EscrowSnapshot,
     },
-    api::{config_state, Config},
 };
 
+/// upper bound on the queries made while waiting for a transaction to be included in a block,
+/// enough for the chain to get past the timeout height of the transaction
+const MAX_TX_QUERIES: u32 = 40;
+/// upper bound on the dseqs checked on chain when reserving a new one
+const MAX_DSEQ_ATTEMPTS: usize = 5;
+/// upper bound on the requests made to fetch the latest block height
+const MAX_BLOCK_HEIGHT_QUERIES: usize = 3;
+/// a block is produced roughly every 6 seconds
+const TX_POLLING_INTERVAL_SECS: u64 = 3;
+
+/// upper bound on the calls made while waiting for other transactions to be signed
+const MAX_SIGNING_LOCK_WAITS: usize = 60;
+const SEQUENCE_MISMATCH_ERROR: &str = "account sequence mismatch";
+
+/// Held while signing and broadcasting a transaction from the account with this address.
+///
+/// Released when dropped, which also happens when the task traps after a call.
+struct SigningLock {
+    address: String,
+}
+
+impl Drop for SigningLock {
+    fn drop(&mut self) {
+        account_sequence_state_mut(|state| state.unlock(&self.address));
+    }
+}
+
+/// Account with the sequence reserved to sign a transaction.
+struct ReservedSequence {
+    address: String,
+    account: BaseAccount,
+    _lock: SigningLock,
+}
+
+/// Why a transaction has not been applied.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TxError {
+    /// the transaction has not been applied and never will be:
+    /// it could not be signed or broadcast, its execution failed or it expired
+    Failed(String),
+    /// the transaction has been broadcast, but it is not known yet whether it will be included in a block
+    Unknown {
+        tx_hash: String,
+        /// the transaction may still be included up to this height, 0 if it never expires
+        timeout_height: u64,
+        reason: String,
+    },
+}
+
+impl From<String> for TxError {
+    fn from(e: String) -> Self {
+        TxError::Failed(e)
+    }
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxError::Failed(e) => write!(f, "{}", e),
+            TxError::Unknown {
+                tx_hash,
+                timeout_height,
+                reason,
+            } => write!(
+                f,
+                "tx {} not confirmed yet, it may still be included up to height {}: {}",
+                tx_hash, timeout_height, reason
+            ),
+        }
+    }
+}
+
+/// Operation on Akash that can be signed together with other operations
+/// in a single transaction, see [AkashService::execute_batch].
+pub enum AkashOperation {
+    Send {
+        to_address: String,
+        amount_uakt: u64,
+    },
+    CreateCertificate {
+        cert_pem: Vec<u8>,
+        pub_key_pem: Vec<u8>,
+    },
+    CreateDeployment {
+        sdl: SdlV3,
+        dseq: u64,
+        deposit_uakt: u64,
+    },
+    DepositDeployment {
+        dseq: u64,
+        amount_uakt: u64,
+    },
+    UpdateDeploymentSdl {
+        sdl: SdlV3,
+        dseq: u64,
+    },
+    CloseDeployment {
+        dseq: u64,
+    },
+    CreateLease {
+        bid_id: BidId,
+    },
+}
+
+impl AkashOperation {
+    /// AKT moved out of the signing account by the operation, excluding the fee.
+    fn spent_uakt(&self) -> u64 {
+        match self {
+            AkashOperation::Send { amount_uakt, .. } => *amount_uakt,
+            AkashOperation::CreateDeployment { deposit_uakt, .. } => *deposit_uakt,
+            AkashOperation::DepositDeployment { amount_uakt, .. } => *amount_uakt,
+            AkashOperation::CreateCertificate { .. }
+            | AkashOperation::UpdateDeploymentSdl { .. }
+            | AkashOperation::CloseDeployment { .. }
+            | AkashOperation::CreateLease { .. } => 0,
+        }
+    }
+
+    fn into_tx_message(self, public_key: &PublicKey) -> Result<TxMessage, String> {
+        match self {
+            AkashOperation::Send {
+                to_address,
+                amount_uakt,
+            } => {
+                let recipient_account_id =
+                    AccountId::from_str(to_address.as_str()).map_err(|e| e.to_string())?;
+                create_send_msg(public_key, recipient_account_id, amount_uakt)
+            }
+            AkashOperation::CreateCertificate {
+                cert_pem,
+                pub_key_pem,
+            } => create_certificate_msg(public_key, cert_pem, pub_key_pem),
+            AkashOperation::CreateDeployment {
+                sdl,
+                dseq,
+                deposit_uakt,
+            } => create_deployment_msg(public_key, &sdl, dseq, deposit_uakt),
+            AkashOperation::DepositDeployment { dseq, amount_uakt } => {
+                deposit_deployment_msg(public_key, dseq, amount_uakt)
+            }
+            AkashOperation::UpdateDeploymentSdl { sdl, dseq } => {
+                update_deployment_sdl_msg(public_key, &sdl, dseq)
+            }
+            AkashOperation::CloseDeployment { dseq } => close_deployment_msg(public_key, dseq),
+            AkashOperation::CreateLease { bid_id } => create_lease_msg(bid_id),
+        }
+    }
+}
+
 pub struct AkashService {
-    deployments_counter_memory: DeploymentsCounterMemory,
+    tx_polling: TxPolling,
+    account: AkashAccount,
 }
 
 impl Default for AkashService {
     fn default() -> Self {
         Self {
-            deployments_counter_memory: init_deployments_counter(),
+            tx_polling: TxPolling::Outcalls {
+                max_queries: MAX_TX_QUERIES,
+            },
+            account: AkashAccount::Canister,
         }
     }
 }
 
 impl AkashService {
+    /// Waits on a timer between the queries made to confirm the transactions.
+    ///
+    /// Only for tasks spawned from timers, see [TxPolling::Timer].
+    pub fn with_timer_polling(mut self) -> Self {
+        self.tx_polling = TxPolling::Timer {
+            interval: Duration::from_secs(TX_POLLING_INTERVAL_SECS),
+            max_queries: MAX_TX_QUERIES,
+        };
+        self
+    }
+
+    /// Signs the transactions and makes the queries with the given account, the canister's account by default.
+    ///
+    /// The transactions of a user's account are funded from the canister's account when needed.
+    pub fn with_account(mut self, account: AkashAccount) -> Self {
+        self.account = account;
+        self
+    }
+
     pub fn get_config(&self) -> Config {
         config_state(|state| state.clone())
     }
 
-    pub async fn address(&self) -> Result<String, String> {
+    /// The key and address of the account, fetching its public key only if it is not cached yet for the configured key.
+    async fn canister_account(&self, account: &AkashAccount) -> Result<CanisterAccount, String> {
         let config = self.get_config();
 
-        let public_key = config.public_key().await?;
+        if let Some(cached) = cached_canister_account(account, config.ecdsa_key()) {
+            return Ok(cached);
+        }
+
+        let public_key = config.public_key(account.derivation_path()).await?;
+        let cached = CanisterAccount::new(
+            config.ecdsa_key().clone(),
+            &public_key,
+            get_account_id_from_public_key(&public_key)?.to_string(),
+        );
+        set_cached_canister_account(account, cached.clone())?;
+
+        Ok(cached)
+    }
+
+    pub async fn public_key(&self) -> Result<PublicKey, String> {
+        self.canister_account(&self.account).await?.public_key()
+    }
+
+    pub async fn account_id(&self) -> Result<AccountId, String> {
+        let address = self.address().await?;
+
+        AccountId::from_str(&address).map_err(|e| format!("invalid cached address: {:?}", e))
+    }
+
+    pub async fn address(&self) -> Result<String, String> {
+        self.canister_account(&self.account)
+            .await
+            .map(|account| account.address)
+    }
 
-        Ok(get_account_id_from_public_key(&public_key)?.to_string())
+    /// The address of the account, available without calls once its public key has been fetched.
+    pub fn cached_address(&self) -> Option<String> {
+        cached_canister_account(&self.account, self.get_config().ecdsa_key())
+            .map(|account| account.address)
     }
 
     pub async fn uakt_balance(&self) -> Result<u64, String> {
-        let config = self.get_config();
+        let public_key = self.public_key().await?;
 
-        let public_key = config.public_key().await?;
+        self.uakt_balance_of(&public_key).await
+    }
+
+    async fn uakt_balance_of(&self, public_key: &PublicKey) -> Result<u64, String> {
+        let config = self.get_config();
 
-        let balance = get_balance(config.tendermint_rpc_url(), &public_key)
+        let balance = get_balance(config.tendermint_rpc_url(), public_key)
             .await
             .map(|coin| coin.amount)?;
 
@@ -64,231 +291,594 @@ impl AkashService {
 
     #[allow(dead_code)]
     pub async fn send(&self, to_address: String, amount: u64) -> Result<String, String> {
-        let config = self.get_config();
+        self.execute_batch(
+            None,
+            vec![AkashOperation::Send {
+                to_address,
+                amount_uakt: amount,
+            }],
+        )
+        .await
+        .map(|result| result.hash)
+        .map_err(|e| e.to_string())
+    }
 
-        let public_key = config.public_key().await?;
-        let rpc_url = config.tendermint_rpc_url();
+    pub async fn create_certificate(
+        &self,
+        cert_pem_base64: String,
+        pub_key_pem_base64: String,
+    ) -> Result<String, String> {
+        let cert_pem = base64_decode(&cert_pem_base64)?;
+        let pub_key_pem = base64_decode(&pub_key_pem_base64)?;
 
-        let account = get_account(rpc_url.clone(), &public_key).await?;
+        self.execute_batch(
+            None,
+            vec![AkashOperation::CreateCertificate {
+                cert_pem,
+                pub_key_pem,
+            }],
+        )
+        .await
+        .map(|result| result.hash)
+        .map_err(|e| e.to_string())
+    }
 
-        let recipient_account_id =
-            AccountId::from_str(to_address.as_str()).map_err(|e| e.to_string())?;
+    /// Creates the deployment on Akash with the given dseq,
+    /// which must be reserved with [AkashService::next_deployment_id] beforehand.
+    pub async fn create_deployment(
+        &self,
+        deployment_id: DeploymentId,
+        sdl: SdlV3,
+        dseq: u64,
+        deposit_uakt: u64,
+    ) -> Result<(String, u64, String), String> {
+        let public_key = self.public_key().await?;
+        let manifest = sdl.manifest_sorted_json();
+
+        let result = self
+            .execute_batch(
+                Some(deployment_id),
+                vec![AkashOperation::CreateDeployment {
+                    sdl,
+                    dseq,
+                    deposit_uakt,
+                }],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        let tx_hash = result.hash.clone();
+
+        let deployment_id = created_deployment_id(&result)?;
+        let expected_owner = get_account_id_from_public_key(&public_key)?.to_string();
+        if deployment_id.owner != expected_owner || deployment_id.dseq != dseq {
+            return Err(format!(
+                "tx {} created deployment {}/{} instead of {}/{}",
+                tx_hash, deployment_id.owner, deployment_id.dseq, expected_owner, dseq
+            ));
+        }
 
-        let tx_raw = create_send_tx(
-            &public_key,
-            recipient_account_id,
-            amount,
-            &account,
-            config.ecdsa_key(),
-            config.chain_id(),
-        )
-        .await?;
+        Ok((tx_hash, deployment_id.dseq, manifest))
+    }
 
-        let tx_hash =
-            ic_tendermint_rpc::broadcast_tx_sync(config.is_mainnet(), rpc_url, tx_raw).await?;
+    /// Reserves the dseq of the next deployment.
+    ///
+    /// Following the Akash convention, the dseq is the height of the latest block.
+    /// It is also greater than the last reserved dseq, so that the deployments created in the same block
+    /// do not collide, and it skips the deployments already on chain, e.g. created by other tools with the same account.
+    pub async fn next_deployment_id(&self) -> Result<u64, String> {
+        let height = self.latest_block_height().await?;
+
+        for _ in 0..MAX_DSEQ_ATTEMPTS {
+            // reserved before querying, so that concurrent calls get a different dseq
+            let dseq = reserve_dseq(height)?;
+
+            if !self.deployment_exists(dseq).await? {
+                return Ok(dseq);
+            }
+        }
 
-        Ok(tx_hash)
+        Err(format!(
+            "no free dseq found after {} attempts",
+            MAX_DSEQ_ATTEMPTS
+        ))
     }
 
-    pub async fn create_certificate(
+    /// The request is retried as it fails if a block is produced while the replicas are making it.
+    pub async fn latest_block_height(&self) -> Result<u64, String> {
+        let config = self.get_config();
+
+        let mut last_error = String::new();
+        for _ in 0..MAX_BLOCK_HEIGHT_QUERIES {
+            match ic_tendermint_rpc::latest_block(config.tendermint_rpc_url()).await {
+                Ok(block) => return Ok(block.block.header.height.value()),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(format!(
+            "could not fetch latest block height: {}",
+            last_error
+        ))
+    }
+
+    pub async fn deposit_deployment(
         &self,
-        cert_pem_base64: String,
-        pub_key_pem_base64: String,
-    ) -> Result<String, String> {
+        deployment_id: DeploymentId,
+        dseq: u64,
+        amount_uakt: u64,
+    ) -> Result<(), TxError> {
+        self.execute_batch(
+            Some(deployment_id),
+            vec![AkashOperation::DepositDeployment { dseq, amount_uakt }],
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Signs the operations in a single transaction, broadcasts it and waits for it to be included in a block.
+    ///
+    /// Batching the operations saves the threshold ECDSA signature and the HTTPS outcalls of a transaction for each of them.
+    /// The operations are executed in order and atomically: if one fails, none of them is applied.
+    ///
+    /// The deployment for which the operations are made, if any, is recorded in the memo of the transaction.
+    ///
+    /// A user's account is first funded from the canister's account with the AKT it misses to pay for the operations and the maximum fee.
+    ///
+    /// Fails with [TxError::Unknown] if the transaction has been broadcast but it is not known yet whether it will be applied.
+    pub async fn execute_batch(
+        &self,
+        deployment_id: Option<DeploymentId>,
+        operations: Vec<AkashOperation>,
+    ) -> Result<ExecTxResult, TxError> {
         let config = self.get_config();
+        let public_key = self.public_key().await?;
+
+        let spent_uakt = operations
+            .iter()
+            .map(AkashOperation::spent_uakt)
+            .sum::<u64>();
+        let msgs = operations
+            .into_iter()
+            .map(|operation| operation.into_tx_message(&public_key))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let AkashAccount::User(_) = self.account {
+            let max_fee_uakt = config
+                .akash_config()
+                .fees
+                .fee_uakt(msgs.iter().map(|msg| msg.max_gas).sum());
+            // the operations are not broadcast if the funding is not confirmed,
+            // the AKT sent anyway stay on the user's account for the next transactions
+            self.fund_account(deployment_id, &public_key, spent_uakt + max_fee_uakt)
+                .await
+                .map_err(|e| TxError::Failed(format!("could not fund account: {}", e)))?;
+        }
+
+        self.sign_and_broadcast(&self.account, &public_key, deployment_id, msgs)
+            .await
+    }
 
-        let public_key = config.public_key().await?;
-        let rpc_url = config.tendermint_rpc_url();
+    /// Sends from the canister's account the AKT missing on the user's account to pay for a transaction.
+    async fn fund_account(
+        &self,
+        deployment_id: Option<DeploymentId>,
+        public_key: &PublicKey,
+        required_uakt: u64,
+    ) -> Result<(), TxError> {
+        let balance_uakt = self.uakt_balance_of(public_key).await?;
+        if balance_uakt >= required_uakt {
+            return Ok(());
+        }
 
-        let cert_pem = base64_decode(&cert_pem_base64)?;
-        let pub_key_pem = base64_decode(&pub_key_pem_base64)?;
+        let canister_public_key = self
+            .canister_account(&AkashAccount::Canister)
+            .await?
+            .public_key()?;
+        let recipient = get_account_id_from_public_key(public_key)?;
+        let amount_uakt = required_uakt - balance_uakt;
+        let send = AkashOperation::Send {
+            to_address: recipient.to_string(),
+            amount_uakt,
+        }
+        .into_tx_message(&canister_public_key)?;
+
+        let result = self
+            .sign_and_broadcast(
+                &AkashAccount::Canister,
+                &canister_public_key,
+                deployment_id,
+                vec![send],
+            )
+            .await?;
+
+        log_info!(
+            format!(
+                "Funded account {} with {} uakt in tx {}",
+                recipient, amount_uakt, result.hash
+            ),
+            "fund_account"
+        );
 
-        let account = get_account(rpc_url.clone(), &public_key).await?;
+        Ok(())
+    }
 
-        let tx_raw = create_certificate_tx(
-            &public_key,
-            cert_pem,
-            pub_key_pem,
-            &account,
-            config.ecdsa_key(),
-            config.chain_id(),
+    async fn sign_and_broadcast(
+        &self,
+        account: &AkashAccount,
+        public_key: &PublicKey,
+        deployment_id: Option<DeploymentId>,
+        msgs: Vec<TxMessage>,
+    ) -> Result<ExecTxResult, TxError> {
+        let config = self.get_config();
+
+        let timeout_height = match config.akash_config().tx_timeout_blocks {
+            0 => 0,
+            timeout_blocks => self.latest_block_height().await? + timeout_blocks,
+        };
+        let tx_config = TxConfig {
+            rpc_url: config.tendermint_rpc_url(),
+            chain_id: config.chain_id(),
+            ecdsa_key: config.ecdsa_key(),
+            derivation_path: account.derivation_path(),
+            fees: &config.akash_config().fees,
+            timeout_height,
+            memo: TxMemo::new(deployment_id.map(|id| id.to_string())),
+        };
+
+        let reserved = self.reserve_sequence(public_key).await?;
+
+        let tx_raw = create_tx(
+            public_key,
+            msgs,
+            reserved.account.sequence,
+            reserved.account.account_number,
+            &tx_config,
         )
         .await?;
 
-        let tx_hash =
-            ic_tendermint_rpc::broadcast_tx_sync(config.is_mainnet(), rpc_url, tx_raw).await?;
+        let tx_hash = self.broadcast(reserved, tx_raw).await?;
+        self.confirm_tx(&tx_hash, timeout_height, self.tx_polling)
+            .await
+    }
 
-        Ok(tx_hash)
+    /// Reserves the next sequence of the account to sign a transaction.
+    ///
+    /// The transactions of an account are signed and broadcast one at a time, so that they reach the chain in the order of their sequences:
+    /// the task waits for the other tasks to broadcast their transactions from the same account first.
+    async fn reserve_sequence(&self, public_key: &PublicKey) -> Result<ReservedSequence, String> {
+        let address = get_account_id_from_public_key(public_key)?;
+
+        let mut waits = 0;
+        while !account_sequence_state_mut(|state| {
+            state.try_lock(address.as_ref(), get_time_nanos())
+        }) {
+            if waits == MAX_SIGNING_LOCK_WAITS {
+                return Err(String::from(
+                    "timed out waiting for other transactions to be signed",
+                ));
+            }
+            waits += 1;
+
+            // timers cannot resume a task that has to reply to a call, so the task yields with a call instead
+            raw_rand().await.map_err(|(code, msg)| {
+                format!("could not wait for signing lock: ({:?}) {}", code, msg)
+            })?;
+        }
+        // the lock is released when dropped, also on the errors below
+        let lock = SigningLock {
+            address: address.to_string(),
+        };
+
+        let account = match account_sequence_state(|state| state.next(address.as_ref())) {
+            Some((account_number, sequence)) => BaseAccount {
+                address: address.clone(),
+                pubkey: Some(*public_key),
+                account_number,
+                sequence,
+            },
+            None => {
+                let config = self.get_config();
+                let account = get_account(config.tendermint_rpc_url(), public_key).await?;
+                account_sequence_state_mut(|state| {
+                    state.sync(address.as_ref(), account.account_number, account.sequence)
+                });
+                account
+            }
+        };
+
+        Ok(ReservedSequence {
+            address: address.to_string(),
+            account,
+            _lock: lock,
+        })
     }
 
-    pub async fn create_deployment(&mut self, sdl: SdlV3) -> Result<(String, u64, String), String> {
+    /// Broadcasts the transaction signed with the reserved sequence, releasing the signing lock afterwards.
+    async fn broadcast(
+        &self,
+        reserved: ReservedSequence,
+        tx_raw: Vec<u8>,
+    ) -> Result<String, String> {
         let config = self.get_config();
 
-        let public_key = config.public_key().await?;
-        let rpc_url = config.tendermint_rpc_url();
-
-        let account = get_account(rpc_url.clone(), &public_key).await?;
-        let dseq = self.next_deployment_id();
-        let deposit = config.akash_config().min_deposit_uakt_amount;
-
-        let tx_raw = create_deployment_tx(
-            &public_key,
-            &sdl,
-            dseq,
-            deposit,
-            &account,
-            config.ecdsa_key(),
-            config.chain_id(),
+        let result = ic_tendermint_rpc::broadcast_tx_sync(
+            config.is_mainnet(),
+            config.tendermint_rpc_url(),
+            tx_raw,
         )
-        .await?;
+        .await;
+
+        match &result {
+            Ok(_) => account_sequence_state_mut(|state| {
+                state.advance(&reserved.address, reserved.account.sequence)
+            }),
+            // e.g. another tool has used the account, the sequence is synced again from the chain
+            Err(e) if e.contains(SEQUENCE_MISMATCH_ERROR) => {
+                log_warn!(
+                    format!(
+                        "Account sequence {} rejected, syncing it from the chain: {}",
+                        reserved.account.sequence, e
+                    ),
+                    "broadcast"
+                );
+                account_sequence_state_mut(|state| state.invalidate(&reserved.address));
+            }
+            // the transaction has been rejected by CheckTx, the sequence has not been used
+            Err(_) => {}
+        }
 
-        let tx_hash =
-            ic_tendermint_rpc::broadcast_tx_sync(config.is_mainnet(), rpc_url, tx_raw).await?;
+        result
+    }
 
-        // print(&format!(
-        //     "[create_deployment] tx_hash: {}, dseq: {}",
-        //     tx_hash, dseq
-        // ));
+    /// Fails if the transaction has not been included in a block yet or if its execution failed.
+    pub async fn check_tx(&self, tx_hash_hex: String) -> Result<ExecTxResult, String> {
+        let config = self.get_config();
 
-        Ok((tx_hash, dseq, sdl.manifest_sorted_json()))
+        ic_tendermint_rpc::tx(config.tendermint_rpc_url(), tx_hash_hex.clone())
+            .await?
+            .ok_or(format!("tx {} not found", tx_hash_hex))?
+            .into_result()
     }
 
-    fn next_deployment_id(&mut self) -> u64 {
-        let mut deployments_counter = *self.deployments_counter_memory.get();
-        deployments_counter += 1;
-        self.deployments_counter_memory
-            .set(deployments_counter)
-            .unwrap();
-        deployments_counter
+    /// Checks once, without waiting, whether a transaction whose outcome was unknown
+    /// has been included in a block or has expired since.
+    pub async fn recheck_tx(
+        &self,
+        tx_hash_hex: &str,
+        timeout_height: u64,
+    ) -> Result<ExecTxResult, TxError> {
+        self.confirm_tx(
+            tx_hash_hex,
+            timeout_height,
+            TxPolling::Outcalls { max_queries: 1 },
+        )
+        .await
     }
 
-    pub async fn deposit_deployment(&self, dseq: u64, amount_uakt: u64) -> Result<(), String> {
+    /// Waits for the transaction to be included in a block or to expire,
+    /// failing with its log if the execution failed.
+    async fn confirm_tx(
+        &self,
+        tx_hash_hex: &str,
+        timeout_height: u64,
+        polling: TxPolling,
+    ) -> Result<ExecTxResult, TxError> {
         let config = self.get_config();
-        let public_key = config.public_key().await?;
-        let rpc_url = config.tendermint_rpc_url();
-
-        let account = get_account(rpc_url.clone(), &public_key).await?;
 
-        let tx_raw = deposit_deployment_tx(
-            &public_key,
-            dseq,
-            amount_uakt,
-            &account,
-            config.ecdsa_key(),
-            config.chain_id(),
+        match ic_tendermint_rpc::wait_for_tx(
+            config.tendermint_rpc_url(),
+            tx_hash_hex.to_string(),
+            timeout_height,
+            polling,
         )
-        .await?;
+        .await
+        {
+            TxStatus::Included(result) => Ok(result.into_result()?),
+            TxStatus::Expired => Err(TxError::Failed(format!(
+                "tx {} expired at height {} without being included in a block",
+                tx_hash_hex, timeout_height
+            ))),
+            TxStatus::Unknown(reason) => Err(TxError::Unknown {
+                tx_hash: tx_hash_hex.to_string(),
+                timeout_height,
+                reason,
+            }),
+        }
+    }
 
-        let _tx_hash =
-            ic_tendermint_rpc::broadcast_tx_sync(config.is_mainnet(), rpc_url, tx_raw).await?;
+    pub async fn create_lease(
+        &self,
+        deployment_id: DeploymentId,
+        bid_id: BidId,
+    ) -> Result<(String, String), String> {
+        let config = self.get_config();
 
-        // print(&format!(
-        //     "[deposit_deployment] tx_hash: {}, dseq: {}",
-        //     tx_hash, dseq
-        // ));
+        let result = self
+            .execute_batch(
+                Some(deployment_id),
+                vec![AkashOperation::CreateLease {
+                    bid_id: bid_id.clone(),
+                }],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        let tx_hash = result.hash.clone();
+
+        let lease_id = LeaseId::from(&bid_id);
+        if !created_lease_ids(&result)?.contains(&lease_id) {
+            return Err(format!(
+                "tx {} did not create the lease for order {}/{} with provider {}",
+                tx_hash, lease_id.gseq, lease_id.oseq, lease_id.provider
+            ));
+        }
 
-        Ok(())
+        let provider = fetch_provider(config.tendermint_rpc_url(), bid_id.provider).await?;
+
+        Ok((tx_hash, provider.host_uri))
     }
 
-    pub async fn update_deployment_sdl(
+    /// Sends the close transaction and waits for it to be included in a block.
+    pub async fn close_deployment(
         &self,
+        deployment_id: DeploymentId,
         dseq: u64,
-        sdl: SdlV3,
-    ) -> Result<(String, u64, String), String> {
+    ) -> Result<String, String> {
+        self.execute_batch(
+            Some(deployment_id),
+            vec![AkashOperation::CloseDeployment { dseq }],
+        )
+        .await
+        .map(|result| result.hash)
+        .map_err(|e| e.to_string())
+    }
+
+    /// The deployment as stored on Akash, `None` if it does not exist.
+    pub async fn deployment(&self, dseq: u64) -> Result<Option<QueryDeploymentResponse>, String> {
         let config = self.get_config();
 
-        let public_key = config.public_key().await?;
-        let rpc_url = config.tendermint_rpc_url();
+        fetch_deployment(config.tendermint_rpc_url(), self.address().await?, dseq).await
+    }
 
-        let account = get_account(rpc_url.clone(), &public_key).await?;
+    /// Leases of the deployment, in any state.
+    pub async fn leases(&self, dseq: u64) -> Result<Vec<Lease>, String> {
+        let config = self.get_config();
 
-        let tx_raw = update_deployment_sdl_tx(
-            &public_key,
-            &sdl,
-            dseq,
-            &account,
-            config.ecdsa_key(),
-            config.chain_id(),
-        )
-        .await?;
+        let leases = fetch_leases(config.tendermint_rpc_url(), self.address().await?, dseq).await?;
 
-        let tx_hash =
-            ic_tendermint_rpc::broadcast_tx_sync(config.is_mainnet(), rpc_url, tx_raw).await?;
+        Ok(leases.into_iter().filter_map(|res| res.lease).collect())
+    }
 
-        // print(&format!(
-        //     "[update_deployment_sdl] tx_hash: {}, dseq: {}",
-        //     tx_hash, dseq
-        // ));
+    /// The bid with the given id as currently stored on chain, `None` if it does not exist.
+    pub async fn bid(&self, bid_id: BidId) -> Result<Option<Bid>, String> {
+        let config = self.get_config();
 
-        Ok((tx_hash, dseq, sdl.manifest_sorted_json()))
+        fetch_bid(config.tendermint_rpc_url(), bid_id).await
     }
 
-    pub async fn check_tx(&self, tx_hash_hex: String) -> Result<(), String> {
+    /// The lease with the given id, `None` if it does not exist.
+    pub async fn lease(&self, lease_id: LeaseId) -> Result<Option<QueryLeaseResponse>, String> {
         let config = self.get_config();
 
-        ic_tendermint_rpc::check_tx(config.tendermint_rpc_url(), tx_hash_hex).await
+        fetch_lease(config.tendermint_rpc_url(), lease_id).await
     }
 
-    pub async fn create_lease(&self, dseq: u64) -> Result<(String, String), String> {
+    /// Leases of the deployment together with the escrow paying for them,
+    /// `None` if the deployment does not exist on Akash.
+    pub async fn lease_status(&self, dseq: u64) -> Result<Option<DeploymentLeaseStatus>, String> {
         let config = self.get_config();
 
-        let public_key = config.public_key().await?;
-        let account_id = get_account_id_from_public_key(&public_key)?;
-        let rpc_url = config.tendermint_rpc_url();
-
-        let account = get_account(rpc_url.clone(), &public_key).await?;
+        let Some(escrow) = self
+            .deployment(dseq)
+            .await?
+            .and_then(|res| res.escrow_account)
+        else {
+            return Ok(None);
+        };
+        let escrow = escrow_snapshot(&escrow)?;
+
+        let leases = self
+            .leases(dseq)
+            .await?
+            .into_iter()
+            .map(DeploymentLease::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(DeploymentLeaseStatus::new(
+            leases,
+            escrow.balance_uakt,
+            escrow.transferred_uakt,
+            config.akash_config().pricing.block_time_secs,
+        )))
+    }
 
-        let bids = fetch_bids(rpc_url.clone(), &account_id, dseq).await?;
-        // print(format!("[create_lease] bids: {:?}", bids));
+    /// Whether the deployment with the given dseq exists on Akash, in any state.
+    pub async fn deployment_exists(&self, dseq: u64) -> Result<bool, String> {
+        self.deployment(dseq).await.map(|res| res.is_some())
+    }
 
-        // TODO: take the "best" bid
-        // SAFETY:
-        // 'create_lease' is called by the 'handle_create_lease' function which is itself called by the 'fetch_bids' function
-        // the latter makes sure that there is at least one bid before calling 'create_lease' so accessing the first bid is safe
-        let bid = bids[0].bid.clone().unwrap();
-        let bid_id = bid.bid_id.unwrap();
+    /// Bids of the deployment for which a lease has already been created.
+    pub async fn leased_bids(&self, dseq: u64) -> Result<Vec<BidId>, String> {
+        let config = self.get_config();
+        let account_id = self.account_id().await?;
 
-        let tx_raw = create_lease_tx(
-            &public_key,
-            bid_id.clone(),
-            &account,
-            config.ecdsa_key(),
-            config.chain_id(),
+        let bids = fetch_bids(
+            config.tendermint_rpc_url(),
+            &account_id,
+            dseq,
+            ACTIVE_BID_STATE,
         )
         .await?;
 
-        let tx_hash =
-            ic_tendermint_rpc::broadcast_tx_sync(config.is_mainnet(), rpc_url, tx_raw).await?;
-
-        // TODO: query lease to see if everything is ok
+        Ok(bids
+            .into_iter()
+            .filter_map(|res| res.bid.and_then(|bid| bid.bid_id))
+            .collect())
+    }
 
-        let provider = fetch_provider(config.tendermint_rpc_url(), bid_id.provider).await?;
+    /// URL of the provider, as advertised on chain.
+    pub async fn provider_url(&self, provider: String) -> Result<String, String> {
+        let config = self.get_config();
 
-        Ok((tx_hash, provider.host_uri))
+        fetch_provider(config.tendermint_rpc_url(), provider)
+            .await
+            .map(|provider| provider.host_uri)
     }
 
-    /// TODO: if closing the deployment fails, we should at least try again.
-    /// Otherwise we don't get the Akash tokens back from the escrow.
-    pub async fn close_deployment(&self, dseq: u64) -> Result<String, String> {
+    /// Fetches from the chain whether the deployment is closed, together with its escrow account.
+    ///
+    /// A deployment that does not exist on chain has nothing locked in escrow
+    /// and is therefore considered closed.
+    pub async fn deployment_escrow(
+        &self,
+        dseq: u64,
+    ) -> Result<(bool, Option<EscrowSnapshot>), String> {
         let config = self.get_config();
 
-        let public_key = config.public_key().await?;
-        let rpc_url = config.tendermint_rpc_url();
+        let Some(res) =
+            fetch_deployment(config.tendermint_rpc_url(), self.address().await?, dseq).await?
+        else {
+            return Ok((true, None));
+        };
+
+        let is_closed = res.deployment.map_or(true, |deployment| {
+            deployment.state == DeploymentState::Closed as i32
+        });
+        let escrow = res
+            .escrow_account
+            .as_ref()
+            .map(escrow_snapshot)
+            .transpose()?;
+
+        Ok((is_closed, escrow))
+    }
+}
 
-        let account = get_account(rpc_url.clone(), &public_key).await?;
+/// Reserves the dseq following the last reserved one, at least the given block height.
+///
+/// The counter is read from stable memory and updated without awaiting in between,
+/// so that concurrent tasks never reserve the same dseq.
+fn reserve_dseq(height: u64) -> Result<u64, String> {
+    let mut counter = init_deployments_counter();
+    let dseq = height.max(*counter.get() + 1);
 
-        let tx_raw = close_deployment_tx(
-            &public_key,
-            dseq,
-            &account,
-            config.ecdsa_key(),
-            config.chain_id(),
-        )
-        .await?;
+    counter
+        .set(dseq)
+        .map_err(|e| format!("could not reserve dseq: {:?}", e))?;
+
+    Ok(dseq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let tx_hash =
-            ic_tendermint_rpc::broadcast_tx_sync(config.is_mainnet(), rpc_url, tx_raw).await?;
+    #[test]
+    fn test_reserve_dseq_at_same_height() {
+        let first = reserve_dseq(100).unwrap();
+        let second = reserve_dseq(100).unwrap();
 
-        Ok(tx_hash)
+        assert_eq!(first, 100);
+        assert_eq!(second, 101);
+        assert_eq!(reserve_dseq(200).unwrap(), 200);
     }
 }
@@ -1,10 +1,102 @@
-use crate::api::{config_state_mut, Config};
+use crate::api::{
+    config_state, config_state_mut, init_per_user_accounts, init_resource_tiers,
+    invalidate_canister_account, log_error, ApiError, Config, PerUserAccountsMemory, ResourceTiers,
+    ResourceTiersMemory,
+};
 
-#[derive(Default)]
-pub struct ConfigService {}
+pub struct ConfigService {
+    resource_tiers_memory: ResourceTiersMemory,
+    per_user_accounts_memory: PerUserAccountsMemory,
+}
+
+impl Default for ConfigService {
+    fn default() -> Self {
+        Self {
+            resource_tiers_memory: init_resource_tiers(),
+            per_user_accounts_memory: init_per_user_accounts(),
+        }
+    }
+}
 
 impl ConfigService {
-    pub fn set_config(&mut self, config: Config) {
+    /// Sets the config, keeping the resource tiers and the per-user accounts mode stored in stable memory
+    /// since they can be edited by the admins.
+    ///
+    /// The cached canister account is cleared if it belongs to a different ECDSA key.
+    pub fn set_config(&mut self, mut config: Config) {
+        config.set_resource_tiers(self.resource_tiers_memory.get().clone());
+        config.set_per_user_accounts(*self.per_user_accounts_memory.get() == 1);
+
+        if let Err(e) = invalidate_canister_account(config.ecdsa_key()) {
+            log_error!(e, "set_config");
+        }
+
         config_state_mut(|state| *state = config)
     }
+
+    pub fn get_resource_tiers(&self) -> ResourceTiers {
+        config_state(|state| state.resource_tiers().clone())
+    }
+
+    pub fn set_resource_tiers(&mut self, resource_tiers: ResourceTiers) -> Result<(), ApiError> {
+        resource_tiers
+            .validate()
+            .map_err(|e| ApiError::invalid_argument(&format!("Invalid resource tiers: {}", e)))?;
+
+        self.resource_tiers_memory
+            .set(resource_tiers.clone())
+            .map_err(|e| ApiError::internal(&format!("Cannot store resource tiers: {:?}", e)))?;
+
+        config_state_mut(|state| state.set_resource_tiers(resource_tiers));
+
+        Ok(())
+    }
+
+    /// Only the new deployments are affected, the existing ones keep the account recorded when they were created.
+    pub fn set_per_user_accounts(&mut self, per_user_accounts: bool) -> Result<(), ApiError> {
+        self.per_user_accounts_memory
+            .set(u8::from(per_user_accounts))
+            .map_err(|e| {
+                ApiError::internal(&format!("Cannot store per-user accounts mode: {:?}", e))
+            })?;
+
+        config_state_mut(|state| state.set_per_user_accounts(per_user_accounts));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use crate::api::{AkashAccount, Deployment, DeploymentPrice, UserId};
+
+    use super::*;
+
+    #[test]
+    fn test_set_per_user_accounts() {
+        let mut config_service = ConfigService::default();
+        let user_id = UserId::new(Principal::anonymous());
+        config_service.set_config(Config::default());
+
+        let existing_deployment = Deployment::new(
+            None,
+            String::from("version: \"3.0\""),
+            user_id,
+            config_state(|state| state.user_akash_account(user_id)),
+            DeploymentPrice::default(),
+            0.0,
+        );
+
+        config_service.set_per_user_accounts(true).unwrap();
+        // as in post_upgrade
+        config_service.set_config(Config::default());
+
+        assert_eq!(
+            config_state(|state| state.user_akash_account(user_id)),
+            AkashAccount::User(user_id)
+        );
+        assert_eq!(existing_deployment.akash_account(), AkashAccount::Canister);
+    }
 }
@@ -1,7 +1,9 @@
 use crate::{
+    akash::sdl::SdlV3,
     api::{
-        config_state, init_deployments, ApiError, Config, Deployment, DeploymentId,
-        DeploymentParams, DeploymentState, DeploymentUpdateWsMessage, DeploymentsMemory, UserId,
+        config_state, init_deployments, AkashAccount, ApiError, AutoTopUp, Config, Deployment,
+        DeploymentId, DeploymentParams, DeploymentPrice, DeploymentRevision, DeploymentState,
+        DeploymentUpdateWsMessage, DeploymentsMemory, PendingDeposit, UserId,
     },
     helpers::{send_canister_update, uakt_to_akt},
 };
@@ -43,58 +45,227 @@ impl DeploymentsService {
     pub async fn init_deployment(
         &mut self,
         user_id: UserId,
-        sdl_params: DeploymentParams,
-        akt_price: f64,
+        sdl_params: Option<DeploymentParams>,
+        sdl: String,
+        price: DeploymentPrice,
         icp_price: f64,
     ) -> Result<DeploymentId, ApiError> {
         let deployment_id = DeploymentId::new()
             .await
             .map_err(|e| ApiError::internal(&format!("Failed to create deployment id: {}", e)))?;
 
-        let deployment = Deployment::new(sdl_params, user_id, akt_price, icp_price);
+        let akash_account = self.get_config().user_akash_account(user_id);
+        let deployment = Deployment::new(sdl_params, sdl, user_id, akash_account, price, icp_price);
 
         self.deployments_memory.insert(deployment_id, deployment);
 
         Ok(deployment_id)
     }
 
+    /// Stores the SDL the deployment has been updated to on Akash.
+    pub fn update_deployment_sdl(
+        &mut self,
+        deployment_id: DeploymentId,
+        sdl: String,
+    ) -> Result<(), ApiError> {
+        let mut deployment = self.get_deployment(&deployment_id)?;
+
+        deployment.update_sdl(sdl);
+        self.deployments_memory.insert(deployment_id, deployment);
+
+        Ok(())
+    }
+
+    pub fn reserve_deployment_dseq(
+        &mut self,
+        deployment_id: DeploymentId,
+        dseq: u64,
+    ) -> Result<(), ApiError> {
+        let mut deployment = self.get_deployment(&deployment_id)?;
+
+        deployment.reserve_dseq(dseq);
+        self.deployments_memory.insert(deployment_id, deployment);
+
+        Ok(())
+    }
+
+    /// Deployments whose creation has not completed yet, which need a timer to make progress.
+    pub fn get_deployments_in_progress(&self) -> Vec<(DeploymentId, Deployment)> {
+        self.deployments_memory
+            .iter()
+            .filter(|(_, deployment)| {
+                matches!(
+                    deployment.state(),
+                    DeploymentState::Initialized
+                        | DeploymentState::DeploymentCreated { .. }
+                        | DeploymentState::LeaseCreated { .. }
+                )
+            })
+            .collect()
+    }
+
+    /// Deployments with a lease that may have changed on chain without the canister knowing it.
+    pub fn get_deployments_to_reconcile(&self) -> Vec<(DeploymentId, Deployment)> {
+        self.deployments_memory
+            .iter()
+            .filter(|(_, deployment)| {
+                let state = deployment.state();
+
+                !state.is_terminal()
+                    && state != DeploymentState::EscrowOverdrawn
+                    && !deployment.leased_orders().is_empty()
+            })
+            .collect()
+    }
+
+    pub fn set_auto_top_up(
+        &mut self,
+        deployment_id: DeploymentId,
+        auto_top_up: Option<AutoTopUp>,
+    ) -> Result<(), ApiError> {
+        let mut deployment = self.get_deployment(&deployment_id)?;
+
+        deployment.set_auto_top_up(auto_top_up);
+        self.deployments_memory.insert(deployment_id, deployment);
+
+        Ok(())
+    }
+
+    pub fn add_pending_deposit(
+        &mut self,
+        deployment_id: DeploymentId,
+        pending_deposit: PendingDeposit,
+    ) -> Result<(), ApiError> {
+        let mut deployment = self.get_deployment(&deployment_id)?;
+
+        deployment.add_pending_deposit(pending_deposit);
+        self.deployments_memory.insert(deployment_id, deployment);
+
+        Ok(())
+    }
+
+    pub fn remove_pending_deposit(
+        &mut self,
+        deployment_id: DeploymentId,
+        tx_hash: &str,
+    ) -> Result<(), ApiError> {
+        let mut deployment = self.get_deployment(&deployment_id)?;
+
+        deployment.remove_pending_deposit(tx_hash);
+        self.deployments_memory.insert(deployment_id, deployment);
+
+        Ok(())
+    }
+
+    /// Leased deployments whose escrow must be kept funded,
+    /// together with the ones whose deposits still have to be settled, whatever their state.
+    pub fn get_deployments_to_top_up(&self) -> Vec<(DeploymentId, Deployment)> {
+        self.deployments_memory
+            .iter()
+            .filter(|(_, deployment)| {
+                !deployment.pending_deposits().is_empty() || deployment.needs_auto_top_up()
+            })
+            .collect()
+    }
+
+    /// Sends an alert about the deployment to its owner, together with its current state.
+    pub fn alert_deployment_owner(
+        &self,
+        deployment_id: DeploymentId,
+        alert: String,
+    ) -> Result<(), ApiError> {
+        let deployment = self.get_deployment(&deployment_id)?;
+
+        send_canister_update(
+            deployment.user_id().principal(),
+            DeploymentUpdateWsMessage::new(deployment_id.to_string(), deployment.state())
+                .with_alert(alert),
+        );
+
+        Ok(())
+    }
+
+    pub fn add_deployment_revision(
+        &mut self,
+        deployment_id: DeploymentId,
+        revision: DeploymentRevision,
+    ) -> Result<(), ApiError> {
+        let mut deployment = self.get_deployment(&deployment_id)?;
+
+        deployment.add_revision(revision);
+        self.deployments_memory.insert(deployment_id, deployment);
+
+        Ok(())
+    }
+
+    /// Records the new revision of the deployment updated on Akash
+    /// and pushes the new manifest to the client, which must send it to the providers.
+    pub fn set_updated_deployment(
+        &mut self,
+        calling_principal: Principal,
+        deployment_id: DeploymentId,
+        tx_hash: String,
+        sdl: String,
+        manifest_sorted_json: String,
+        manifest_version: &[u8],
+    ) -> Result<(), ApiError> {
+        let revision = DeploymentRevision::new(
+            Some(tx_hash.clone()),
+            sdl,
+            manifest_sorted_json.clone(),
+            manifest_version,
+        );
+        let deployment_update = DeploymentState::Updated {
+            tx_hash,
+            manifest_version: revision.manifest_version.clone(),
+        };
+
+        self.add_deployment_revision(deployment_id, revision)?;
+        self.update_deployment_state(
+            calling_principal,
+            deployment_id,
+            deployment_update.clone(),
+            false,
+        )?;
+
+        send_canister_update(
+            calling_principal,
+            DeploymentUpdateWsMessage::new(deployment_id.to_string(), deployment_update)
+                .with_manifest(manifest_sorted_json),
+        );
+
+        Ok(())
+    }
+
     pub fn check_deployment_state(&self, deployment_id: DeploymentId) -> Result<(), ApiError> {
         let deployment_state = self.get_deployment(&deployment_id)?.state();
 
-        // if the deployment is not in the Active or LeaseCreated state, it cannot be closed, updated or deposited being made to it
-        // either it fails while being creating deployment and lease (and thus there is no need to close it)
-        // or it eventually gets to the LeaseCreated or Active state (and from that point on it can be closed, updated or deposited being made to it)
-        match deployment_state {
-            DeploymentState::Initialized
-            | DeploymentState::DeploymentCreated { .. }
-            | DeploymentState::Active
-            | DeploymentState::LeaseCreated { .. } => {
-                log_info!(
-                    format!(
-                        "[Deployment {}]: Closing deployment in {:?} state",
-                        deployment_id, deployment_state
-                    ),
-                    "check_deployment_state"
-                );
-
-                Ok(())
-            }
-            DeploymentState::FailedOnCanister { .. } | DeploymentState::FailedOnClient { .. } => {
-                log_warn!(
-                    format!(
-                        "[Deployment {}]: Deployment is already in {:?} state",
-                        deployment_id, deployment_state
-                    ),
-                    "check_deployment_state"
-                );
-
-                Ok(())
-            }
-            DeploymentState::Closed => Err(ApiError::permission_denied(&format!(
-                "Cannot close deployment. Current state: {:?}",
-                deployment_state
-            ))),
+        // a deployment can be closed in any state but Closed:
+        // either it fails while being created (and closing it just cleans up on Akash)
+        // or it eventually gets to the LeaseCreated or Active state
+        deployment_state.check_transition(&DeploymentState::Closed)?;
+
+        if deployment_state.is_terminal() {
+            log_warn!(
+                format!(
+                    "[Deployment {}]: Deployment is already in {} state",
+                    deployment_id,
+                    deployment_state.name()
+                ),
+                "check_deployment_state"
+            );
+        } else {
+            log_info!(
+                format!(
+                    "[Deployment {}]: Closing deployment in {} state",
+                    deployment_id,
+                    deployment_state.name()
+                ),
+                "check_deployment_state"
+            );
         }
+
+        Ok(())
     }
 
     pub fn set_failed_deployment(
@@ -113,17 +284,13 @@ impl DeploymentsService {
         )
     }
 
-    pub fn get_akash_deployment_info(
+    /// The account that owns the deployment on Akash.
+    pub fn get_akash_account(
         &self,
         deployment_id: &DeploymentId,
-    ) -> Result<Option<u64>, ApiError> {
-        self.deployments_memory
-            .get(deployment_id)
-            .map(|deployment| deployment.get_akash_info())
-            .ok_or(ApiError::not_found(&format!(
-                "Deployment {} not found",
-                deployment_id
-            )))
+    ) -> Result<AkashAccount, ApiError> {
+        self.get_deployment(deployment_id)
+            .map(|deployment| deployment.akash_account())
     }
 
     pub fn set_close_deployment(
@@ -150,25 +317,7 @@ impl DeploymentsService {
             ApiError::not_found(&format!("Deployment {} not found", deployment_id))
         })?;
 
-        // the deployment can still be closed if it failed on canister,
-        // it may just fail closing but we let this case pass
-        if matches!(deployment.state(), DeploymentState::FailedOnCanister { .. })
-            && !matches!(deployment_update, DeploymentState::Closed)
-        {
-            return Err(ApiError::internal(&format!(
-                "Deployment {} already failed",
-                deployment_id
-            )));
-        }
-
-        if let DeploymentState::Closed = deployment.state() {
-            return Err(ApiError::internal(&format!(
-                "Deployment {} already closed",
-                deployment_id
-            )));
-        }
-
-        deployment.update_state(deployment_update.clone());
+        deployment.update_state(deployment_update.clone())?;
         self.deployments_memory.insert(deployment_id, deployment);
 
         if notify_client {
@@ -181,10 +330,30 @@ impl DeploymentsService {
         Ok(())
     }
 
-    // TODO: calculate price based on the deployment specs
-    pub fn get_deployment_akt_price(&self) -> f64 {
-        let uakt_amount = self.get_config().akash_config().min_deposit_uakt_amount;
+    /// Computes the price of running the SDL for the given duration.
+    ///
+    /// The escrow deposit covers the estimated cost of the resources for the whole duration,
+    /// but it is never lower than the minimum deposit required by Akash.
+    pub fn get_deployment_price(
+        &self,
+        sdl: &SdlV3,
+        duration_secs: Option<u64>,
+    ) -> Result<DeploymentPrice, ApiError> {
+        let config = self.get_config();
+        let akash_config = config.akash_config();
+        let pricing = &akash_config.pricing;
+
+        let requests = sdl
+            .resource_requests()
+            .map_err(|e| ApiError::invalid_argument(&format!("Invalid SDL resources: {}", e)))?;
+
+        let cost_uakt = (pricing.uakt_per_block(&requests) * pricing.blocks(duration_secs) as f64)
+            .ceil() as u64;
+        let escrow_deposit_uakt = cost_uakt.max(akash_config.min_deposit_uakt_amount);
 
-        uakt_to_akt(uakt_amount)
+        Ok(DeploymentPrice {
+            escrow_deposit_uakt,
+            akt: uakt_to_akt(escrow_deposit_uakt) * (1.0 + pricing.orchestration_margin),
+        })
     }
 }
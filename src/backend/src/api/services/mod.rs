@@ -4,6 +4,7 @@ mod config_service;
 mod deployments_service;
 mod ledger_service;
 mod log_service;
+mod pending_closes_service;
 mod users_service;
 
 pub(super) use access_control_service::*;
@@ -12,4 +13,5 @@ pub(super) use config_service::*;
 pub(super) use deployments_service::*;
 pub(super) use ledger_service::*;
 pub use log_service::*;
+pub(super) use pending_closes_service::*;
 pub(super) use users_service::*;
@@ -0,0 +1,84 @@
+use utils::get_time_nanos;
+
+use crate::api::{
+    init_pending_closes, DeploymentId, EscrowSnapshot, PendingClose, PendingClosesMemory,
+};
+
+pub struct PendingClosesService {
+    pending_closes_memory: PendingClosesMemory,
+}
+
+impl Default for PendingClosesService {
+    fn default() -> Self {
+        Self {
+            pending_closes_memory: init_pending_closes(),
+        }
+    }
+}
+
+impl PendingClosesService {
+    /// Adds the deployment to the queue of the closes to be confirmed on chain,
+    /// recording the outcome of the first close transaction.
+    pub fn enqueue(
+        &mut self,
+        deployment_id: DeploymentId,
+        dseq: u64,
+        escrow_before_close: Option<EscrowSnapshot>,
+        close_result: Result<String, String>,
+    ) {
+        let now = get_time_nanos();
+        let mut pending_close = self
+            .pending_closes_memory
+            .get(&deployment_id)
+            .unwrap_or_else(|| PendingClose::new(dseq, escrow_before_close, now));
+
+        pending_close.record_attempt(close_result, now);
+
+        self.pending_closes_memory
+            .insert(deployment_id, pending_close);
+    }
+
+    /// Adds the deployment to the queue, to be closed only if its creation is applied up to the given height.
+    pub fn enqueue_awaiting_creation(
+        &mut self,
+        deployment_id: DeploymentId,
+        dseq: u64,
+        until_height: u64,
+    ) {
+        if self.pending_closes_memory.contains_key(&deployment_id) {
+            return;
+        }
+
+        self.pending_closes_memory.insert(
+            deployment_id,
+            PendingClose::awaiting_creation(dseq, until_height, get_time_nanos()),
+        );
+    }
+
+    pub fn get_pending_closes(&self) -> Vec<(DeploymentId, PendingClose)> {
+        self.pending_closes_memory.iter().collect()
+    }
+
+    /// Returns the pending closes that have to be checked now.
+    pub fn get_due_pending_closes(&self) -> Vec<(DeploymentId, PendingClose)> {
+        let now = get_time_nanos();
+
+        self.pending_closes_memory
+            .iter()
+            .filter(|(_, pending_close)| pending_close.is_due(now))
+            .collect()
+    }
+
+    pub fn update_pending_close(
+        &mut self,
+        deployment_id: DeploymentId,
+        pending_close: PendingClose,
+    ) {
+        self.pending_closes_memory
+            .insert(deployment_id, pending_close);
+    }
+
+    pub fn remove_pending_close(&mut self, deployment_id: &DeploymentId) -> Option<PendingClose> {
+        self.pending_closes_memory.remove(deployment_id)
+    }
+}
@@ -1,17 +1,20 @@
 use candid::Principal;
 
 use crate::api::{
-    init_users, log_info, ApiError, UpdateUserInput, User, UserId, UserRole, UsersMemory,
+    init_akt_ledger, init_users, log_info, AktLedgerEntry, AktLedgerEntryKind, AktLedgerMemory,
+    ApiError, UpdateUserInput, User, UserId, UserRole, UsersMemory,
 };
 
 pub struct UsersService {
     users_memory: UsersMemory,
+    akt_ledger_memory: AktLedgerMemory,
 }
 
 impl Default for UsersService {
     fn default() -> Self {
         Self {
             users_memory: init_users(),
+            akt_ledger_memory: init_akt_ledger(),
         }
     }
 }
@@ -66,10 +69,17 @@ impl UsersService {
         }
 
         user.add_payment(payment_block_height);
-        user.add_to_akt_balance(amount_akt);
+        let updated_balance = user.add_to_akt_balance(amount_akt);
         self.users_memory.insert(user_id, user);
 
-        Ok(())
+        self.record_akt_ledger_entry(AktLedgerEntry::new(
+            user_id,
+            AktLedgerEntryKind::Payment {
+                payment_block_height,
+            },
+            amount_akt,
+            updated_balance,
+        ))
     }
 
     pub fn get_user_akt_balance(&self, user_id: &UserId) -> Result<f64, ApiError> {
@@ -79,7 +89,12 @@ impl UsersService {
             .ok_or_else(|| ApiError::not_found("User not found"))
     }
 
-    pub fn charge_user(&mut self, user_id: UserId, amount_akt: f64) -> Result<(), ApiError> {
+    pub fn charge_user(
+        &mut self,
+        user_id: UserId,
+        amount_akt: f64,
+        kind: AktLedgerEntryKind,
+    ) -> Result<(), ApiError> {
         let mut user = self
             .users_memory
             .get(&user_id)
@@ -105,7 +120,52 @@ impl UsersService {
 
         self.users_memory.insert(user_id, user);
 
-        Ok(())
+        self.record_akt_ledger_entry(AktLedgerEntry::new(
+            user_id,
+            kind,
+            -amount_akt,
+            updated_balance,
+        ))
+    }
+
+    /// Adds back to the user's balance the AKT that the canister received on their behalf.
+    pub fn credit_user(
+        &mut self,
+        user_id: UserId,
+        amount_akt: f64,
+        kind: AktLedgerEntryKind,
+    ) -> Result<f64, ApiError> {
+        let mut user = self
+            .users_memory
+            .get(&user_id)
+            .ok_or_else(|| ApiError::not_found("User not found"))?;
+
+        let updated_balance = user.add_to_akt_balance(amount_akt);
+        self.users_memory.insert(user_id, user);
+
+        self.record_akt_ledger_entry(AktLedgerEntry::new(
+            user_id,
+            kind,
+            amount_akt,
+            updated_balance,
+        ))?;
+
+        Ok(updated_balance)
+    }
+
+    /// Returns the AKT ledger entries, only the ones of the given user if provided.
+    pub fn get_akt_ledger_entries(&self, user_id: Option<&UserId>) -> Vec<AktLedgerEntry> {
+        self.akt_ledger_memory
+            .iter()
+            .filter(|entry| user_id.map_or(true, |user_id| entry.user_id == *user_id))
+            .collect()
+    }
+
+    fn record_akt_ledger_entry(&mut self, entry: AktLedgerEntry) -> Result<(), ApiError> {
+        self.akt_ledger_memory
+            .append(&entry)
+            .map(|_| ())
+            .map_err(|e| ApiError::internal(&format!("Cannot write AKT ledger entry: {:?}", e)))
     }
 
     pub fn update_user(
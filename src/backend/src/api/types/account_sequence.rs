@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use super::TimestampNs;
+
+/// a task holding the signing lock for longer than this has trapped without releasing it
+const SIGNING_LOCK_TIMEOUT_NS: u64 = 5 * 60 * 1_000_000_000;
+
+/// Sequences of the canister's Akash accounts, cached to sign a transaction
+/// without waiting for the previous ones to be included in a block.
+#[derive(Debug, Default)]
+pub struct AccountSequenceState {
+    /// (account number, next sequence) by address, missing until synced from the chain
+    accounts: BTreeMap<String, (u64, u64)>,
+    /// time since which a task is signing and broadcasting a transaction, by address of the signing account
+    signing_since: BTreeMap<String, TimestampNs>,
+}
+
+impl AccountSequenceState {
+    /// Returns the (account number, next sequence) of the account, if synced.
+    pub fn next(&self, address: &str) -> Option<(u64, u64)> {
+        self.accounts.get(address).copied()
+    }
+
+    pub fn sync(&mut self, address: &str, account_number: u64, sequence: u64) {
+        self.accounts
+            .insert(address.to_string(), (account_number, sequence));
+    }
+
+    /// Records that a transaction with the given sequence has been accepted by the chain.
+    pub fn advance(&mut self, address: &str, used_sequence: u64) {
+        if let Some((_, next_sequence)) = self.accounts.get_mut(address) {
+            *next_sequence = (*next_sequence).max(used_sequence + 1);
+        }
+    }
+
+    /// Forces the sequence of the account to be synced from the chain before its next transaction.
+    pub fn invalidate(&mut self, address: &str) {
+        self.accounts.remove(address);
+    }
+
+    /// Acquires the signing lock of the account, unless it is held by another task.
+    ///
+    /// The accounts have independent sequences, so they can sign at the same time.
+    pub fn try_lock(&mut self, address: &str, now: TimestampNs) -> bool {
+        if self
+            .signing_since
+            .get(address)
+            .is_some_and(|since| now.saturating_sub(*since) < SIGNING_LOCK_TIMEOUT_NS)
+        {
+            return false;
+        }
+
+        self.signing_since.insert(address.to_string(), now);
+        true
+    }
+
+    pub fn unlock(&mut self, address: &str) {
+        self.signing_since.remove(address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        let mut state = AccountSequenceState::default();
+        state.advance("akash1a", 3);
+        assert_eq!(state.next("akash1a"), None);
+
+        state.sync("akash1a", 7, 3);
+        state.advance("akash1a", 3);
+        assert_eq!(state.next("akash1a"), Some((7, 4)));
+
+        // sequences are never handed out twice
+        state.advance("akash1a", 2);
+        assert_eq!(state.next("akash1a"), Some((7, 4)));
+
+        // the accounts have independent sequences
+        state.sync("akash1b", 8, 0);
+        state.advance("akash1b", 0);
+        assert_eq!(state.next("akash1a"), Some((7, 4)));
+        assert_eq!(state.next("akash1b"), Some((8, 1)));
+
+        state.invalidate("akash1a");
+        assert_eq!(state.next("akash1a"), None);
+        assert_eq!(state.next("akash1b"), Some((8, 1)));
+    }
+
+    #[test]
+    fn test_signing_lock() {
+        let mut state = AccountSequenceState::default();
+        assert!(state.try_lock("akash1a", 0));
+        assert!(!state.try_lock("akash1a", 1));
+
+        // the accounts have independent locks
+        assert!(state.try_lock("akash1b", 1));
+        state.unlock("akash1b");
+        assert!(!state.try_lock("akash1a", 1));
+
+        state.unlock("akash1a");
+        assert!(state.try_lock("akash1a", 2));
+
+        // a stale lock is taken over
+        assert!(state.try_lock("akash1a", 2 + SIGNING_LOCK_TIMEOUT_NS));
+    }
+}
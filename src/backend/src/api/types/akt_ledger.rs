@@ -0,0 +1,64 @@
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use std::borrow::Cow;
+
+use utils::get_time_nanos;
+
+use super::{TimestampNs, UserId};
+
+/// Movement of a user's AKT balance.
+///
+/// The entries of all the users add up to the AKT held by the canister on their behalf.
+#[derive(Debug, CandidType, Deserialize, Clone)]
+pub struct AktLedgerEntry {
+    pub timestamp: TimestampNs,
+    pub user_id: UserId,
+    pub kind: AktLedgerEntryKind,
+    /// positive when the balance is credited, negative when it is charged
+    pub amount_akt: f64,
+    /// balance of the user after the entry
+    pub balance_akt: f64,
+}
+
+impl AktLedgerEntry {
+    pub fn new(
+        user_id: UserId,
+        kind: AktLedgerEntryKind,
+        amount_akt: f64,
+        balance_akt: f64,
+    ) -> Self {
+        Self {
+            timestamp: get_time_nanos(),
+            user_id,
+            kind,
+            amount_akt,
+            balance_akt,
+        }
+    }
+}
+
+#[derive(Debug, CandidType, Deserialize, Clone, PartialEq, Eq)]
+pub enum AktLedgerEntryKind {
+    /// ICP payment converted to AKT
+    Payment { payment_block_height: u64 },
+    /// initial deposit in the escrow of a new deployment
+    DeploymentCreation,
+    /// additional deposit in the escrow of a deployment
+    DeploymentDeposit { deployment_id: String },
+    /// funds returned from the escrow of a closed deployment
+    EscrowRefund { deployment_id: String },
+    /// deposit charged to the user that could not be made in the escrow
+    DeploymentDepositRefund { deployment_id: String },
+}
+
+impl Storable for AktLedgerEntry {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,84 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+use super::TimestampNs;
+
+const SECS_IN_HOUR: u64 = 60 * 60;
+
+/// How long the escrow of a deployment can pay for its leases.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum Runway {
+    Blocks(u64),
+    Hours(u64),
+}
+
+impl Runway {
+    pub fn blocks(&self, block_time_secs: f64) -> u64 {
+        match self {
+            Runway::Blocks(blocks) => *blocks,
+            Runway::Hours(hours) => {
+                (hours.saturating_mul(SECS_IN_HOUR) as f64 / block_time_secs).ceil() as u64
+            }
+        }
+    }
+}
+
+/// Settings chosen by the user to keep the escrow of a deployment funded.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct AutoTopUpSettings {
+    /// the escrow is topped up when its runway gets shorter than this
+    pub min_runway: Runway,
+    /// amount deposited in the escrow at each top-up
+    pub amount_uakt: u64,
+}
+
+impl AutoTopUpSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if matches!(self.min_runway, Runway::Blocks(0) | Runway::Hours(0)) {
+            return Err(String::from("min runway must be greater than 0"));
+        }
+
+        if self.amount_uakt == 0 {
+            return Err(String::from("top-up amount must be greater than 0"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct AutoTopUp {
+    pub settings: AutoTopUpSettings,
+    pub last_top_up_at: Option<TimestampNs>,
+    /// set when the job stopped topping up the escrow, e.g. because the user's balance is too low,
+    /// the user has to set the settings again to resume it
+    pub stopped_reason: Option<String>,
+}
+
+impl AutoTopUp {
+    pub fn new(settings: AutoTopUpSettings) -> Self {
+        Self {
+            settings,
+            last_top_up_at: None,
+            stopped_reason: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.stopped_reason.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runway_blocks() {
+        assert_eq!(Runway::Blocks(10).blocks(6.0), 10);
+        assert_eq!(Runway::Hours(1).blocks(6.0), 600);
+        assert_eq!(Runway::Hours(1).blocks(7.0), 515);
+        // user supplied hours do not overflow
+        assert_eq!(Runway::Hours(u64::MAX).blocks(1.0), u64::MAX);
+    }
+}
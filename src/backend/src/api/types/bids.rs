@@ -0,0 +1,106 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+/// Strategy used to pick the bid to accept among the open bids
+/// received for a deployment.
+///
+/// The filters are applied first, then the remaining bids are ranked
+/// and the first one is accepted.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct BidSelectionStrategy {
+    /// how the eligible bids are ranked
+    pub ranking: BidRanking,
+    /// which providers are allowed to win the bid
+    pub providers: ProviderFilter,
+    /// attributes (key, value) that the provider must advertise on chain
+    pub required_attributes: Vec<(String, String)>,
+    /// minimum number of auditors that must have signed the provider's attributes
+    pub min_audit_signatures: u32,
+    /// if not empty, only the signatures of these auditors are counted
+    pub auditors: Vec<String>,
+}
+
+impl Default for BidSelectionStrategy {
+    fn default() -> Self {
+        Self {
+            ranking: BidRanking::LowestPrice,
+            providers: ProviderFilter::Any,
+            required_attributes: vec![],
+            min_audit_signatures: 0,
+            auditors: vec![],
+        }
+    }
+}
+
+impl BidSelectionStrategy {
+    pub fn requires_provider_attributes(&self) -> bool {
+        !self.required_attributes.is_empty()
+    }
+
+    pub fn requires_audit(&self) -> bool {
+        self.min_audit_signatures > 0
+    }
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum BidRanking {
+    /// the bid with the lowest price per block wins
+    LowestPrice,
+    /// the bid that was created first wins
+    FirstReceived,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum ProviderFilter {
+    Any,
+    /// only the listed provider addresses can win the bid
+    AllowList(Vec<String>),
+    /// the listed provider addresses can never win the bid
+    DenyList(Vec<String>),
+}
+
+impl ProviderFilter {
+    pub fn allows(&self, provider: &str) -> bool {
+        match self {
+            ProviderFilter::Any => true,
+            ProviderFilter::AllowList(providers) => providers.iter().any(|p| p == provider),
+            ProviderFilter::DenyList(providers) => !providers.iter().any(|p| p == provider),
+        }
+    }
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum BidSelectionMode {
+    /// the canister selects the bid using the deployment's [BidSelectionStrategy]
+    Automatic,
+    /// the canister waits for the user to accept one of the received bids
+    Manual,
+}
+
+/// Bid received from a provider, as shown to the user.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct DeploymentBid {
+    pub provider: String,
+    pub gseq: u32,
+    pub oseq: u32,
+    pub price: BidPrice,
+    /// block height at which the bid was created
+    pub created_at: i64,
+    pub resources_offered: Vec<BidResourceOffer>,
+}
+
+/// Price per block of a bid.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BidPrice {
+    pub denom: String,
+    pub amount: f64,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct BidResourceOffer {
+    pub count: u32,
+    pub cpu_millis: u64,
+    pub memory_bytes: u64,
+    pub storage_bytes: u64,
+    pub gpu_units: u64,
+}
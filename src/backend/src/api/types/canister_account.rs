@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use cosmrs::{crypto::PublicKey, proto::cosmos::crypto::secp256k1::PubKey};
+use ic_stable_structures::{storable::Bound, Storable};
+
+use crate::helpers::EcdsaKeyIds;
+
+use super::UserId;
+
+/// derivation path component of the keys derived for the users
+const USER_DERIVATION_PREFIX: &[u8] = b"user";
+
+/// Akash account signing the transactions of a deployment.
+#[derive(CandidType, Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum AkashAccount {
+    /// the account of the canister's ECDSA key, which holds the AKT of the canister
+    Canister,
+    /// the account of the key derived for the user, funded from the canister's account
+    User(UserId),
+}
+
+impl AkashAccount {
+    pub fn derivation_path(&self) -> Vec<Vec<u8>> {
+        match self {
+            AkashAccount::Canister => vec![],
+            AkashAccount::User(user_id) => vec![
+                USER_DERIVATION_PREFIX.to_vec(),
+                user_id.principal().as_slice().to_vec(),
+            ],
+        }
+    }
+}
+
+/// Public key of an [AkashAccount] and the Akash address derived from it.
+///
+/// Cached because fetching the public key requires a call to the management canister.
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct CanisterAccount {
+    /// the key the public key has been fetched for, the account is stale if the configured key is different
+    pub ecdsa_key: EcdsaKeyIds,
+    /// compressed SEC1 encoding of the secp256k1 public key
+    pub public_key: Vec<u8>,
+    pub address: String,
+}
+
+impl CanisterAccount {
+    pub fn new(ecdsa_key: EcdsaKeyIds, public_key: &PublicKey, address: String) -> Self {
+        Self {
+            ecdsa_key,
+            public_key: public_key.to_bytes(),
+            address,
+        }
+    }
+
+    pub fn public_key(&self) -> Result<PublicKey, String> {
+        PublicKey::try_from(PubKey {
+            key: self.public_key.clone(),
+        })
+        .map_err(|e| format!("invalid cached public key: {}", e))
+    }
+
+    pub fn is_for(&self, ecdsa_key: &EcdsaKeyIds) -> bool {
+        self.ecdsa_key == *ecdsa_key
+    }
+}
+
+/// The [CanisterAccount] stored in stable memory, `None` until the public key is fetched.
+#[derive(CandidType, Clone, Debug, Default, Deserialize)]
+pub struct CanisterAccountCache(pub Option<CanisterAccount>);
+
+impl Storable for CanisterAccountCache {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::SigningKey;
+
+    use super::*;
+
+    #[test]
+    fn test_cached_public_key_round_trip() {
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let public_key = PublicKey::from(*signing_key.verifying_key());
+        let account = CanisterAccount::new(EcdsaKeyIds::TestKey1, &public_key, String::new());
+
+        let cached =
+            CanisterAccountCache::from_bytes(CanisterAccountCache(Some(account)).to_bytes())
+                .0
+                .unwrap();
+
+        assert_eq!(cached.public_key(), Ok(public_key));
+        assert!(cached.is_for(&EcdsaKeyIds::TestKey1));
+        assert!(!cached.is_for(&EcdsaKeyIds::ProductionKey1));
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        let user_id = UserId::new(candid::Principal::from_slice(&[1, 2, 3]));
+
+        assert!(AkashAccount::Canister.derivation_path().is_empty());
+        assert_eq!(
+            AkashAccount::User(user_id).derivation_path(),
+            vec![b"user".to_vec(), vec![1, 2, 3]]
+        );
+    }
+}
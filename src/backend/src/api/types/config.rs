@@ -6,6 +6,11 @@ use ic_stable_structures::{storable::Bound, Storable};
 
 use crate::helpers::{get_public_key, EcdsaKeyIds};
 
+use super::{AkashAccount, FeeConfig, PricingConfig, ResourceTiers, UserId};
+
+/// about 2 minutes, with a block produced every 6 seconds
+pub const DEFAULT_TX_TIMEOUT_BLOCKS: u64 = 20;
+
 #[derive(CandidType, Clone, Deserialize)]
 pub struct AkashConfig {
     /// Can be obtained from <akash-api-endpoint>/cosmos/params/v1beta1/params?subspace=deployment&key=MinDeposits
@@ -14,6 +19,16 @@ pub struct AkashConfig {
     /// - sandbox: **5_000_000 uakt** (5 AKT)
     /// - mainnet: **500_000 uakt** (0.5 AKT)
     pub min_deposit_uakt_amount: u64,
+    /// rates used to compute the price of the deployments
+    pub pricing: PricingConfig,
+    /// policy used to compute the gas limit and the fee of the transactions
+    pub fees: FeeConfig,
+    /// blocks after the latest one within which a transaction must be included,
+    /// so that it cannot be executed after it has been retried. 0 disables the timeout
+    pub tx_timeout_blocks: u64,
+    /// if enabled, the deployments of each user are created from an account derived for the user,
+    /// funded from the canister's account, instead of from the canister's account itself
+    pub per_user_accounts: bool,
 }
 
 #[derive(CandidType, Clone, Deserialize)]
@@ -23,6 +38,7 @@ pub struct Config {
     tendermint_rpc_url: String,
     chain_id: String,
     akash_config: AkashConfig,
+    resource_tiers: ResourceTiers,
 }
 
 impl Config {
@@ -38,6 +54,7 @@ impl Config {
             chain_id: "akashnet-2".to_string(),
             tendermint_rpc_url: tendermint_rpc_url.to_string(),
             akash_config,
+            resource_tiers: ResourceTiers::default(),
         }
     }
 
@@ -57,8 +74,29 @@ impl Config {
         &self.akash_config
     }
 
-    pub async fn public_key(&self) -> Result<PublicKey, String> {
-        get_public_key(self.ecdsa_key()).await
+    pub fn resource_tiers(&self) -> &ResourceTiers {
+        &self.resource_tiers
+    }
+
+    pub fn set_resource_tiers(&mut self, resource_tiers: ResourceTiers) {
+        self.resource_tiers = resource_tiers;
+    }
+
+    pub fn set_per_user_accounts(&mut self, per_user_accounts: bool) {
+        self.akash_config.per_user_accounts = per_user_accounts;
+    }
+
+    pub async fn public_key(&self, derivation_path: Vec<Vec<u8>>) -> Result<PublicKey, String> {
+        get_public_key(self.ecdsa_key(), derivation_path).await
+    }
+
+    /// The account that signs the transactions of the user's new deployments and certificates.
+    pub fn user_akash_account(&self, user_id: UserId) -> AkashAccount {
+        if self.akash_config.per_user_accounts {
+            AkashAccount::User(user_id)
+        } else {
+            AkashAccount::Canister
+        }
     }
 
     pub fn chain_id(&self) -> &str {
@@ -76,7 +114,12 @@ impl Default for Config {
             tendermint_rpc_url: "https://rpc.sandbox-01.aksh.pw".to_string(),
             akash_config: AkashConfig {
                 min_deposit_uakt_amount: 5_000_000,
+                pricing: PricingConfig::default(),
+                fees: FeeConfig::default(),
+                tx_timeout_blocks: DEFAULT_TX_TIMEOUT_BLOCKS,
+                per_user_accounts: false,
             },
+            resource_tiers: ResourceTiers::default(),
         }
     }
 }
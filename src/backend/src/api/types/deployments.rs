@@ -1,36 +1,107 @@
-use super::{TimestampNs, UserId};
+use super::{
+    AkashAccount, ApiError, AutoTopUp, BidSelectionMode, BidSelectionStrategy, DeploymentBid,
+    DeploymentPrice, ResourceTier, TimestampNs, UserId,
+};
 use candid::{CandidType, Decode, Encode};
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::{Deserialize, Serialize};
-use std::borrow::Cow;
+use std::{borrow::Cow, fmt::Display};
 use utils::{get_time_nanos, Uuid};
 
 pub type DeploymentId = Uuid;
+/// Identifies an order of the deployment on Akash as (gseq, oseq).
+pub type OrderSeq = (u32, u32);
 
 #[derive(Debug, CandidType, Deserialize, Clone)]
 pub struct Deployment {
-    params: DeploymentParams,
+    /// `None` for deployments created from an SDL supplied by the user
+    params: Option<DeploymentParams>,
+    /// SDL deployed on Akash, either supplied by the user or generated from the params,
+    /// `None` for deployments created before the SDL was stored
+    sdl: Option<String>,
     user_id: UserId,
     state_history: Vec<(TimestampNs, DeploymentState)>,
     akt_price: f64,
     icp_price: f64,
+    bid_selection: Option<BidSelectionStrategy>,
+    escrow_deposit_uakt: Option<u64>,
+    /// SDL and manifest sent to Akash on creation and on every update,
+    /// `None` for deployments created before the revisions were stored
+    revisions: Option<Vec<DeploymentRevision>>,
+    /// dseq reserved for the deployment before broadcasting the transaction that creates it,
+    /// so that the creation can be retried without creating a second deployment on Akash
+    reserved_dseq: Option<u64>,
+    auto_top_up: Option<AutoTopUp>,
+    /// deposits to the escrow charged to the user whose transaction is not confirmed yet,
+    /// checked again by the auto top-up job, which refunds the user for the failed ones
+    pending_deposits: Option<Vec<PendingDeposit>>,
+    /// account that owns the deployment on Akash,
+    /// `None` for deployments created before the users could have their own account
+    akash_account: Option<AkashAccount>,
 }
 
 impl Deployment {
-    pub fn new(params: DeploymentParams, user_id: UserId, akt_price: f64, icp_price: f64) -> Self {
+    pub fn new(
+        params: Option<DeploymentParams>,
+        sdl: String,
+        user_id: UserId,
+        akash_account: AkashAccount,
+        price: DeploymentPrice,
+        icp_price: f64,
+    ) -> Self {
+        let bid_selection = params
+            .as_ref()
+            .and_then(|params| params.bid_selection.clone())
+            .unwrap_or_default();
+
         Self {
             params,
+            sdl: Some(sdl),
             user_id,
             state_history: vec![(get_time_nanos(), DeploymentState::Initialized)],
-            akt_price,
+            akt_price: price.akt,
             icp_price,
+            bid_selection: Some(bid_selection),
+            escrow_deposit_uakt: Some(price.escrow_deposit_uakt),
+            revisions: Some(vec![]),
+            reserved_dseq: None,
+            auto_top_up: None,
+            pending_deposits: Some(vec![]),
+            akash_account: Some(akash_account),
         }
     }
 
-    pub fn params(&self) -> DeploymentParams {
+    pub fn params(&self) -> Option<DeploymentParams> {
         self.params.clone()
     }
 
+    pub fn sdl(&self) -> Option<String> {
+        self.sdl.clone()
+    }
+
+    /// Replaces the SDL after the deployment has been updated on Akash.
+    ///
+    /// The price does not change, as the updated SDL must request the same resources.
+    pub fn update_sdl(&mut self, sdl: String) {
+        self.sdl = Some(sdl);
+    }
+
+    /// Revisions of the deployment on Akash, the first one being the creation.
+    pub fn revisions(&self) -> Vec<DeploymentRevision> {
+        self.revisions.clone().unwrap_or_default()
+    }
+
+    pub fn add_revision(&mut self, revision: DeploymentRevision) {
+        self.revisions.get_or_insert_with(Vec::new).push(revision);
+    }
+
+    /// Deployments created from an SDL supplied by the user always select the bids automatically.
+    pub fn is_manual_bid_selection(&self) -> bool {
+        self.params
+            .as_ref()
+            .is_some_and(|params| params.is_manual_bid_selection())
+    }
+
     pub fn user_id(&self) -> UserId {
         self.user_id
     }
@@ -47,18 +118,105 @@ impl Deployment {
         self.icp_price
     }
 
+    /// Amount deposited in the escrow when creating the deployment on Akash,
+    /// `None` for deployments created before the price was computed from the resources.
+    pub fn escrow_deposit_uakt(&self) -> Option<u64> {
+        self.escrow_deposit_uakt
+    }
+
+    /// Strategy used to select the provider's bid for this deployment.
+    ///
+    /// Deployments created before the strategy was stored fall back to the default one.
+    pub fn bid_selection(&self) -> BidSelectionStrategy {
+        self.bid_selection.clone().unwrap_or_default()
+    }
+
     pub fn user_owns_deployment(&self, user_id: &UserId) -> bool {
         self.user_id == *user_id
     }
 
-    pub fn update_state(&mut self, update: DeploymentState) {
+    /// Checks the transition against the table of [DeploymentState::can_transition_to].
+    ///
+    /// With multiple orders, the client may mark the deployment as active or update it
+    /// before every order has a lease, so the leases of the pending orders can still be created.
+    pub fn check_transition(&self, next: &DeploymentState) -> Result<(), IllegalStateTransition> {
+        let state = self.state();
+
+        if matches!(
+            state,
+            DeploymentState::Active | DeploymentState::Updated { .. }
+        ) && matches!(next, DeploymentState::LeaseCreated { .. })
+            && !self.pending_orders().is_empty()
+        {
+            return Ok(());
+        }
+
+        state.check_transition(next)
+    }
+
+    /// Moves the deployment to the new state, if the transition is legal.
+    pub fn update_state(&mut self, update: DeploymentState) -> Result<(), IllegalStateTransition> {
+        self.check_transition(&update)?;
+
         self.state_history.push((get_time_nanos(), update));
+
+        Ok(())
     }
 
     pub fn get_history(&self) -> Vec<(u64, DeploymentState)> {
         self.state_history.clone()
     }
 
+    pub fn auto_top_up(&self) -> Option<AutoTopUp> {
+        self.auto_top_up.clone()
+    }
+
+    pub fn set_auto_top_up(&mut self, auto_top_up: Option<AutoTopUp>) {
+        self.auto_top_up = auto_top_up;
+    }
+
+    /// Whether the escrow must be kept funded by the auto top-up, which only pays for leased deployments.
+    pub fn needs_auto_top_up(&self) -> bool {
+        self.auto_top_up
+            .as_ref()
+            .is_some_and(|auto_top_up| auto_top_up.is_active())
+            && matches!(
+                self.state(),
+                DeploymentState::LeaseCreated { .. }
+                    | DeploymentState::Active
+                    | DeploymentState::Updated { .. }
+            )
+    }
+
+    pub fn pending_deposits(&self) -> Vec<PendingDeposit> {
+        self.pending_deposits.clone().unwrap_or_default()
+    }
+
+    pub fn add_pending_deposit(&mut self, pending_deposit: PendingDeposit) {
+        self.pending_deposits
+            .get_or_insert_with(Vec::new)
+            .push(pending_deposit);
+    }
+
+    pub fn remove_pending_deposit(&mut self, tx_hash: &str) {
+        if let Some(pending_deposits) = &mut self.pending_deposits {
+            pending_deposits.retain(|pending_deposit| pending_deposit.tx_hash != tx_hash);
+        }
+    }
+
+    /// Deployments created before the users could have their own account are owned by the canister's account.
+    pub fn akash_account(&self) -> AkashAccount {
+        self.akash_account.unwrap_or(AkashAccount::Canister)
+    }
+
+    pub fn reserved_dseq(&self) -> Option<u64> {
+        self.reserved_dseq
+    }
+
+    pub fn reserve_dseq(&mut self, dseq: u64) {
+        self.reserved_dseq = Some(dseq);
+    }
+
     pub fn get_akash_info(&self) -> Option<u64> {
         self.state_history
             .iter()
@@ -67,6 +225,54 @@ impl Deployment {
             .first()
             .cloned()
     }
+
+    /// Orders opened on Akash for the deployment, one for each placement group of the SDL.
+    pub fn orders(&self) -> Vec<OrderSeq> {
+        let groups = self
+            .state_history
+            .iter()
+            .find_map(|(_, update)| match update {
+                // deployments created before the groups were tracked had a single group
+                DeploymentState::DeploymentCreated { groups, .. } => Some(groups.unwrap_or(1)),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        (1..=groups).map(|gseq| (gseq, 1)).collect()
+    }
+
+    /// Orders for which a lease has already been created.
+    pub fn leased_orders(&self) -> Vec<OrderSeq> {
+        self.state_history
+            .iter()
+            .filter_map(|(_, update)| match update {
+                DeploymentState::LeaseCreated { order, .. } => Some(order.unwrap_or((1, 1))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Orders that are still waiting for a lease.
+    pub fn pending_orders(&self) -> Vec<OrderSeq> {
+        let leased_orders = self.leased_orders();
+
+        self.orders()
+            .into_iter()
+            .filter(|order| !leased_orders.contains(order))
+            .collect()
+    }
+
+    /// Bids most recently handed over to the user.
+    pub fn received_bids(&self) -> Vec<DeploymentBid> {
+        self.state_history
+            .iter()
+            .rev()
+            .find_map(|(_, update)| match update {
+                DeploymentState::BidsReceived { bids } => Some(bids.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl Storable for Deployment {
@@ -81,20 +287,91 @@ impl Storable for Deployment {
     const BOUND: Bound = Bound::Unbounded;
 }
 
-/// Deployment update sent to the client via IC WebSocket
+/// SDL and manifest deployed on Akash by a transaction.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct DeploymentRevision {
+    pub created_at: TimestampNs,
+    /// `None` if the transaction that created the deployment has been lost, see [DeploymentState::DeploymentCreated]
+    pub tx_hash: Option<String>,
+    pub sdl: String,
+    pub manifest_sorted_json: String,
+    /// hex encoded hash of the manifest, as stored in the deployment on chain
+    pub manifest_version: String,
+}
+
+impl DeploymentRevision {
+    pub fn new(
+        tx_hash: Option<String>,
+        sdl: String,
+        manifest_sorted_json: String,
+        manifest_version: &[u8],
+    ) -> Self {
+        Self {
+            created_at: get_time_nanos(),
+            tx_hash,
+            sdl,
+            manifest_sorted_json,
+            manifest_version: hex::encode(manifest_version),
+        }
+    }
+}
+
+/// Deposit to the escrow broadcast without knowing yet whether it has been applied.
 #[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct PendingDeposit {
+    pub tx_hash: String,
+    /// the deposit may still be applied up to this height
+    pub timeout_height: u64,
+    pub amount_uakt: u64,
+    /// whether the deposit was made by the auto top-up or by the user
+    pub is_auto_top_up: bool,
+}
+
+/// Deployment update sent to the client via IC WebSocket
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub enum DeploymentState {
     Initialized,
     DeploymentCreated {
-        tx_hash: String,
+        /// `None` if the deployment was found on chain when retrying its creation,
+        /// the result of the transaction that created it having been lost
+        tx_hash: Option<String>,
         dseq: u64,
         manifest_sorted_json: String,
+        /// number of placement groups, each one opening an order on Akash
+        groups: Option<u32>,
+    },
+    /// open bids waiting to be accepted by the user,
+    /// only reached when the bid selection mode is [BidSelectionMode::Manual]
+    BidsReceived {
+        bids: Vec<DeploymentBid>,
     },
+    /// a lease is created for each order of the deployment
     LeaseCreated {
         tx_hash: String,
         provider_url: String,
+        /// order the lease was created for, `None` for leases created
+        /// before multiple orders were supported, which are always on order (1, 1)
+        order: Option<OrderSeq>,
+        /// why the bid was selected automatically, `None` if the user accepted it
+        /// or for leases created before the reason was recorded
+        selection_reason: Option<String>,
     },
     Active,
+    /// the deployment has been updated on Akash with a new manifest,
+    /// which the client must send to the providers
+    Updated {
+        tx_hash: String,
+        /// hex encoded hash of the new manifest
+        manifest_version: String,
+    },
+    /// the provider closed the lease of the order on Akash, found by the reconciliation job
+    LeaseClosedByProvider {
+        order: OrderSeq,
+        provider: String,
+    },
+    /// the escrow account ran out of funds and Akash closed the deployment,
+    /// found by the reconciliation job
+    EscrowOverdrawn,
     Closed,
     FailedOnCanister {
         reason: String,
@@ -111,6 +388,99 @@ impl DeploymentState {
             _ => None,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeploymentState::Initialized => "Initialized",
+            DeploymentState::DeploymentCreated { .. } => "DeploymentCreated",
+            DeploymentState::BidsReceived { .. } => "BidsReceived",
+            DeploymentState::LeaseCreated { .. } => "LeaseCreated",
+            DeploymentState::Active => "Active",
+            DeploymentState::Updated { .. } => "Updated",
+            DeploymentState::LeaseClosedByProvider { .. } => "LeaseClosedByProvider",
+            DeploymentState::EscrowOverdrawn => "EscrowOverdrawn",
+            DeploymentState::Closed => "Closed",
+            DeploymentState::FailedOnCanister { .. } => "FailedOnCanister",
+            DeploymentState::FailedOnClient { .. } => "FailedOnClient",
+        }
+    }
+
+    /// Whether the deployment is closed on Akash, or is being closed.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            DeploymentState::Closed
+                | DeploymentState::FailedOnCanister { .. }
+                | DeploymentState::FailedOnClient { .. }
+        )
+    }
+
+    /// Transition table of the deployment's lifecycle.
+    pub fn can_transition_to(&self, next: &DeploymentState) -> bool {
+        use DeploymentState::*;
+
+        match (self, next) {
+            (Closed, _) => false,
+            // failed deployments may still have to be closed on Akash,
+            // which can fail on the canister in turn
+            (FailedOnCanister { .. } | FailedOnClient { .. }, Closed) => true,
+            (FailedOnClient { .. }, FailedOnCanister { .. }) => true,
+            (FailedOnCanister { .. } | FailedOnClient { .. }, _) => false,
+            // any deployment still running can fail on the canister or be closed by the user
+            (_, FailedOnCanister { .. } | Closed) => true,
+            (Initialized, DeploymentCreated { .. }) => true,
+            (DeploymentCreated { .. }, BidsReceived { .. } | LeaseCreated { .. }) => true,
+            (BidsReceived { .. }, LeaseCreated { .. }) => true,
+            // with multiple orders, a lease is created for each one of them
+            (
+                LeaseCreated { .. },
+                LeaseCreated { .. } | Active | Updated { .. } | FailedOnClient { .. },
+            ) => true,
+            (Active, Updated { .. }) => true,
+            (Updated { .. }, Updated { .. } | Active | FailedOnClient { .. }) => true,
+            // the leases are checked against the chain by the reconciliation job
+            (
+                LeaseCreated { .. } | Active | Updated { .. },
+                LeaseClosedByProvider { .. } | EscrowOverdrawn,
+            ) => true,
+            (LeaseClosedByProvider { .. }, LeaseClosedByProvider { .. } | EscrowOverdrawn) => true,
+            _ => false,
+        }
+    }
+
+    pub fn check_transition(&self, next: &DeploymentState) -> Result<(), IllegalStateTransition> {
+        if !self.can_transition_to(next) {
+            return Err(IllegalStateTransition {
+                from: self.name(),
+                to: next.name(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned when a deployment is moved to a state that cannot follow its current one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IllegalStateTransition {
+    pub from: &'static str,
+    pub to: &'static str,
+}
+
+impl Display for IllegalStateTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Illegal deployment state transition from {} to {}",
+            self.from, self.to
+        )
+    }
+}
+
+impl From<IllegalStateTransition> for ApiError {
+    fn from(e: IllegalStateTransition) -> Self {
+        ApiError::conflict(&e.to_string())
+    }
 }
 
 impl Storable for DeploymentState {
@@ -146,6 +516,21 @@ pub struct DeploymentParams {
     pub volume_mount: Option<String>,
     /// command to run in the container
     pub command: Vec<String>,
+    /// strategy used to select the provider,
+    /// if not provided the default strategy (lowest price) is used
+    pub bid_selection: Option<BidSelectionStrategy>,
+    /// whether the bid is selected by the canister or by the user,
+    /// if not provided the bid is selected automatically
+    pub bid_selection_mode: Option<BidSelectionMode>,
+    /// how long the deployment is expected to run, used to compute its price,
+    /// if not provided the default duration is used
+    pub duration_secs: Option<u64>,
+}
+
+impl DeploymentParams {
+    pub fn is_manual_bid_selection(&self) -> bool {
+        matches!(self.bid_selection_mode, Some(BidSelectionMode::Manual))
+    }
 }
 
 impl DeploymentParams {
@@ -162,6 +547,9 @@ impl DeploymentParams {
                 storage: StorageSize::Small,
                 volume_mount: None,
                 command: vec![],
+                bid_selection: None,
+                bid_selection_mode: None,
+                duration_secs: None,
             },
         }
     }
@@ -209,6 +597,21 @@ impl DeploymentParamsBuilder {
         self
     }
 
+    pub fn bid_selection(mut self, bid_selection: BidSelectionStrategy) -> Self {
+        self.inner.bid_selection = Some(bid_selection);
+        self
+    }
+
+    pub fn bid_selection_mode(mut self, bid_selection_mode: BidSelectionMode) -> Self {
+        self.inner.bid_selection_mode = Some(bid_selection_mode);
+        self
+    }
+
+    pub fn duration_secs(mut self, duration_secs: u64) -> Self {
+        self.inner.duration_secs = Some(duration_secs);
+        self
+    }
+
     pub fn build(self) -> DeploymentParams {
         self.inner
     }
@@ -241,15 +644,19 @@ pub enum CpuSize {
     Small,
     Medium,
     Large,
+    /// explicit number of CPU units, e.g. "0.5" or "500m"
+    Custom {
+        units: String,
+    },
 }
 
 impl CpuSize {
-    pub fn to_unit(&self) -> String {
+    pub fn to_unit(&self, tier: &ResourceTier) -> String {
         match self {
-            // TODO: configure the tiers
-            CpuSize::Small => "0.5".to_string(),
-            CpuSize::Medium => "0.5".to_string(),
-            CpuSize::Large => "0.5".to_string(),
+            CpuSize::Small => tier.small.clone(),
+            CpuSize::Medium => tier.medium.clone(),
+            CpuSize::Large => tier.large.clone(),
+            CpuSize::Custom { units } => units.clone(),
         }
     }
 }
@@ -259,15 +666,19 @@ pub enum MemorySize {
     Small,
     Medium,
     Large,
+    /// explicit memory size, e.g. "768Mi"
+    Custom {
+        size: String,
+    },
 }
 
 impl MemorySize {
-    pub fn to_size(&self) -> String {
+    pub fn to_size(&self, tier: &ResourceTier) -> String {
         match self {
-            // TODO: configure the tiers
-            MemorySize::Small => "512Mi".to_string(),
-            MemorySize::Medium => "512Mi".to_string(),
-            MemorySize::Large => "512Mi".to_string(),
+            MemorySize::Small => tier.small.clone(),
+            MemorySize::Medium => tier.medium.clone(),
+            MemorySize::Large => tier.large.clone(),
+            MemorySize::Custom { size } => size.clone(),
         }
     }
 }
@@ -277,15 +688,295 @@ pub enum StorageSize {
     Small,
     Medium,
     Large,
+    /// explicit storage size, e.g. "10Gi"
+    Custom {
+        size: String,
+    },
 }
 
 impl StorageSize {
-    pub fn to_size(&self) -> String {
+    pub fn to_size(&self, tier: &ResourceTier) -> String {
         match self {
-            // TODO: configure the tiers
-            StorageSize::Small => "512Mi".to_string(),
-            StorageSize::Medium => "512Mi".to_string(),
-            StorageSize::Large => "512Mi".to_string(),
+            StorageSize::Small => tier.small.clone(),
+            StorageSize::Medium => tier.medium.clone(),
+            StorageSize::Large => tier.large.clone(),
+            StorageSize::Custom { size } => size.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use super::*;
+
+    fn all_states() -> Vec<DeploymentState> {
+        vec![
+            DeploymentState::Initialized,
+            DeploymentState::DeploymentCreated {
+                tx_hash: Some(String::from("tx_hash")),
+                dseq: 1,
+                manifest_sorted_json: String::from("[]"),
+                groups: Some(1),
+            },
+            DeploymentState::BidsReceived { bids: vec![] },
+            DeploymentState::LeaseCreated {
+                tx_hash: String::from("tx_hash"),
+                provider_url: String::from("https://provider.akash.network"),
+                order: Some((1, 1)),
+                selection_reason: None,
+            },
+            DeploymentState::Active,
+            DeploymentState::Updated {
+                tx_hash: String::from("tx_hash"),
+                manifest_version: String::from("version"),
+            },
+            DeploymentState::LeaseClosedByProvider {
+                order: (1, 1),
+                provider: String::from("provider"),
+            },
+            DeploymentState::EscrowOverdrawn,
+            DeploymentState::Closed,
+            DeploymentState::FailedOnCanister {
+                reason: String::from("reason"),
+            },
+            DeploymentState::FailedOnClient {
+                reason: String::from("reason"),
+            },
+        ]
+    }
+
+    fn legal_transitions() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("Initialized", "DeploymentCreated"),
+            ("Initialized", "Closed"),
+            ("Initialized", "FailedOnCanister"),
+            ("DeploymentCreated", "BidsReceived"),
+            ("DeploymentCreated", "LeaseCreated"),
+            ("DeploymentCreated", "Closed"),
+            ("DeploymentCreated", "FailedOnCanister"),
+            ("BidsReceived", "LeaseCreated"),
+            ("BidsReceived", "Closed"),
+            ("BidsReceived", "FailedOnCanister"),
+            ("LeaseCreated", "LeaseCreated"),
+            ("LeaseCreated", "Active"),
+            ("LeaseCreated", "Updated"),
+            ("LeaseCreated", "Closed"),
+            ("LeaseCreated", "FailedOnCanister"),
+            ("LeaseCreated", "FailedOnClient"),
+            ("LeaseCreated", "LeaseClosedByProvider"),
+            ("LeaseCreated", "EscrowOverdrawn"),
+            ("Active", "Updated"),
+            ("Active", "Closed"),
+            ("Active", "FailedOnCanister"),
+            ("Active", "LeaseClosedByProvider"),
+            ("Active", "EscrowOverdrawn"),
+            ("Updated", "Updated"),
+            ("Updated", "Active"),
+            ("Updated", "Closed"),
+            ("Updated", "FailedOnCanister"),
+            ("Updated", "FailedOnClient"),
+            ("Updated", "LeaseClosedByProvider"),
+            ("Updated", "EscrowOverdrawn"),
+            ("LeaseClosedByProvider", "LeaseClosedByProvider"),
+            ("LeaseClosedByProvider", "EscrowOverdrawn"),
+            ("LeaseClosedByProvider", "Closed"),
+            ("LeaseClosedByProvider", "FailedOnCanister"),
+            ("EscrowOverdrawn", "Closed"),
+            ("EscrowOverdrawn", "FailedOnCanister"),
+            ("FailedOnCanister", "Closed"),
+            ("FailedOnClient", "Closed"),
+            ("FailedOnClient", "FailedOnCanister"),
+        ]
+    }
+
+    #[test]
+    fn test_transition_table() {
+        let legal_transitions = legal_transitions();
+
+        for from in all_states() {
+            for to in all_states() {
+                let expected = legal_transitions.contains(&(from.name(), to.name()));
+
+                assert_eq!(
+                    from.can_transition_to(&to),
+                    expected,
+                    "transition from {} to {}",
+                    from.name(),
+                    to.name()
+                );
+                assert_eq!(from.check_transition(&to).is_ok(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_is_final() {
+        for to in all_states() {
+            assert_eq!(
+                DeploymentState::Closed.check_transition(&to),
+                Err(IllegalStateTransition {
+                    from: "Closed",
+                    to: to.name(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_state() {
+        let mut deployment = Deployment::new(
+            None,
+            String::from("version: \"3.0\""),
+            UserId::new(Principal::anonymous()),
+            AkashAccount::Canister,
+            DeploymentPrice::default(),
+            0.0,
+        );
+
+        let err = deployment
+            .update_state(DeploymentState::Active)
+            .unwrap_err();
+        assert_eq!(
+            ApiError::from(err),
+            ApiError::conflict("Illegal deployment state transition from Initialized to Active")
+        );
+        assert_eq!(deployment.state(), DeploymentState::Initialized);
+
+        deployment
+            .update_state(DeploymentState::FailedOnCanister {
+                reason: String::from("reason"),
+            })
+            .unwrap();
+        deployment.update_state(DeploymentState::Closed).unwrap();
+        assert_eq!(deployment.state(), DeploymentState::Closed);
+        assert_eq!(deployment.get_history().len(), 3);
+    }
+
+    #[test]
+    fn test_lease_created_while_orders_pending() {
+        let lease_created = |order| DeploymentState::LeaseCreated {
+            tx_hash: String::from("tx_hash"),
+            provider_url: String::from("https://provider.akash.network"),
+            order: Some(order),
+            selection_reason: None,
+        };
+        let updated = DeploymentState::Updated {
+            tx_hash: String::from("tx_hash"),
+            manifest_version: String::from("version"),
+        };
+
+        let mut deployment = Deployment::new(
+            None,
+            String::from("version: \"3.0\""),
+            UserId::new(Principal::anonymous()),
+            AkashAccount::Canister,
+            DeploymentPrice::default(),
+            0.0,
+        );
+        deployment
+            .update_state(DeploymentState::DeploymentCreated {
+                tx_hash: Some(String::from("tx_hash")),
+                dseq: 1,
+                manifest_sorted_json: String::from("[]"),
+                groups: Some(3),
+            })
+            .unwrap();
+        deployment.update_state(lease_created((1, 1))).unwrap();
+
+        // (from, to, expected) with orders still pending
+        let transitions = vec![
+            (DeploymentState::Active, lease_created((2, 1)), true),
+            (updated.clone(), lease_created((3, 1)), true),
+            (DeploymentState::Active, DeploymentState::Initialized, false),
+        ];
+        for (from, to, expected) in transitions {
+            deployment.update_state(from.clone()).unwrap();
+            assert_eq!(
+                deployment.check_transition(&to).is_ok(),
+                expected,
+                "transition from {} to {}",
+                from.name(),
+                to.name()
+            );
+            if expected {
+                deployment.update_state(to).unwrap();
+            }
+        }
+
+        // once every order has a lease, the table applies again
+        assert!(deployment.pending_orders().is_empty());
+        assert_eq!(deployment.state(), DeploymentState::Active);
+        assert_eq!(
+            deployment.check_transition(&lease_created((1, 1))),
+            Err(IllegalStateTransition {
+                from: "Active",
+                to: "LeaseCreated",
+            })
+        );
+    }
+
+    #[test]
+    fn test_pending_deposits() {
+        let pending_deposit = |tx_hash: &str| PendingDeposit {
+            tx_hash: String::from(tx_hash),
+            timeout_height: 100,
+            amount_uakt: 1_000_000,
+            is_auto_top_up: false,
+        };
+
+        let mut deployment = Deployment::new(
+            None,
+            String::from("version: \"3.0\""),
+            UserId::new(Principal::anonymous()),
+            AkashAccount::Canister,
+            DeploymentPrice::default(),
+            0.0,
+        );
+        deployment.add_pending_deposit(pending_deposit("tx_hash_1"));
+        deployment.add_pending_deposit(pending_deposit("tx_hash_2"));
+
+        deployment.remove_pending_deposit("tx_hash_1");
+        assert_eq!(
+            deployment.pending_deposits(),
+            vec![pending_deposit("tx_hash_2")]
+        );
+
+        // removing a deposit already settled is a no-op
+        deployment.remove_pending_deposit("tx_hash_1");
+        assert_eq!(deployment.pending_deposits().len(), 1);
+    }
+
+    #[test]
+    fn test_decode_deployment_created_with_text_tx_hash() {
+        // shape of the state stored before the hash could be missing
+        #[derive(CandidType)]
+        enum StoredDeploymentState {
+            DeploymentCreated {
+                tx_hash: String,
+                dseq: u64,
+                manifest_sorted_json: String,
+                groups: Option<u32>,
+            },
+        }
+
+        let bytes = Encode!(&StoredDeploymentState::DeploymentCreated {
+            tx_hash: String::from("tx_hash"),
+            dseq: 1,
+            manifest_sorted_json: String::from("[]"),
+            groups: Some(1),
+        })
+        .unwrap();
+
+        assert_eq!(
+            Decode!(&bytes, DeploymentState).unwrap(),
+            DeploymentState::DeploymentCreated {
+                tx_hash: Some(String::from("tx_hash")),
+                dseq: 1,
+                manifest_sorted_json: String::from("[]"),
+                groups: Some(1),
+            }
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use candid::{CandidType, Deserialize};
+
+/// The gas limit of a transaction is the gas used when simulating it, multiplied by `gas_multiplier`,
+/// which must not exceed the maximum gas of its messages. The fee is the gas limit times `gas_price_uakt`.
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct FeeConfig {
+    /// margin over the simulated gas, as the state may change before the transaction is executed
+    pub gas_multiplier: f64,
+    /// must be at least the minimum gas price accepted by the validators
+    pub gas_price_uakt: f64,
+}
+
+impl FeeConfig {
+    /// Fails if the gas limit exceeds `max_gas`, as capping it could make the transaction run out of gas.
+    pub fn gas_limit(&self, simulated_gas: u64, max_gas: u64) -> Result<u64, String> {
+        let gas_limit = (simulated_gas as f64 * self.gas_multiplier).ceil() as u64;
+
+        if gas_limit > max_gas {
+            return Err(format!(
+                "gas limit {} (simulated gas {} x {}) exceeds max gas {}",
+                gas_limit, simulated_gas, self.gas_multiplier, max_gas
+            ));
+        }
+
+        Ok(gas_limit)
+    }
+
+    pub fn fee_uakt(&self, gas_limit: u64) -> u64 {
+        (gas_limit as f64 * self.gas_price_uakt).ceil() as u64
+    }
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            gas_multiplier: 1.5,
+            gas_price_uakt: 0.025,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_limit() {
+        let fees = FeeConfig::default();
+
+        assert_eq!(fees.gas_limit(100_000, 800_000), Ok(150_000));
+        assert_eq!(fees.gas_limit(500_000, 750_000), Ok(750_000));
+        assert!(fees.gas_limit(700_000, 800_000).is_err());
+        assert_eq!(fees.fee_uakt(150_000), 3_750);
+    }
+}
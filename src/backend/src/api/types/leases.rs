@@ -0,0 +1,71 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+/// State of a lease on Akash.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum LeaseState {
+    Invalid,
+    Active,
+    /// the escrow of the deployment could not pay for the lease
+    InsufficientFunds,
+    Closed,
+}
+
+/// Lease of a deployment, as stored on Akash.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct DeploymentLease {
+    pub provider: String,
+    pub gseq: u32,
+    pub oseq: u32,
+    pub state: LeaseState,
+    pub price_per_block_uakt: f64,
+    /// block height at which the lease was created
+    pub created_at: i64,
+    /// block height at which the lease was closed, if closed
+    pub closed_on: Option<i64>,
+}
+
+/// Leases of a deployment together with the escrow paying for them.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct DeploymentLeaseStatus {
+    pub leases: Vec<DeploymentLease>,
+    /// price per block of all the active leases
+    pub price_per_block_uakt: f64,
+    /// funds still available in the escrow
+    pub escrow_balance_uakt: f64,
+    /// funds paid to the providers so far
+    pub escrow_transferred_uakt: f64,
+    /// blocks until the escrow runs out, `None` if there are no active leases to pay
+    pub estimated_runway_blocks: Option<u64>,
+    /// estimated seconds until the escrow runs out, based on the configured block time
+    pub estimated_runway_secs: Option<u64>,
+}
+
+impl DeploymentLeaseStatus {
+    pub fn new(
+        leases: Vec<DeploymentLease>,
+        escrow_balance_uakt: f64,
+        escrow_transferred_uakt: f64,
+        block_time_secs: f64,
+    ) -> Self {
+        let price_per_block_uakt = leases
+            .iter()
+            .filter(|lease| lease.state == LeaseState::Active)
+            .map(|lease| lease.price_per_block_uakt)
+            .sum::<f64>();
+
+        let estimated_runway_blocks = (price_per_block_uakt > 0.0)
+            .then(|| (escrow_balance_uakt / price_per_block_uakt).floor() as u64);
+        let estimated_runway_secs =
+            estimated_runway_blocks.map(|blocks| (blocks as f64 * block_time_secs).floor() as u64);
+
+        Self {
+            leases,
+            price_per_block_uakt,
+            escrow_balance_uakt,
+            escrow_transferred_uakt,
+            estimated_runway_blocks,
+            estimated_runway_secs,
+        }
+    }
+}
@@ -1,16 +1,36 @@
+mod account_sequence;
+mod akt_ledger;
+mod auto_top_up;
+mod bids;
+mod canister_account;
 mod config;
 mod date_time;
 mod deployments;
+mod fees;
+mod leases;
 mod log;
+mod pending_closes;
+mod pricing;
+mod resource_tiers;
 mod result;
 mod time;
 mod users;
 mod websocket;
 
+pub use account_sequence::*;
+pub use akt_ledger::*;
+pub use auto_top_up::*;
+pub use bids::*;
+pub use canister_account::*;
 pub(super) use config::*;
 pub(super) use date_time::*;
 pub use deployments::*;
+pub use fees::*;
+pub use leases::*;
 pub(super) use log::*;
+pub use pending_closes::*;
+pub use pricing::*;
+pub use resource_tiers::*;
 pub(super) use result::*;
 pub(super) use time::*;
 pub(super) use users::*;
@@ -0,0 +1,165 @@
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use std::borrow::Cow;
+
+use super::TimestampNs;
+
+const NANOS_IN_SECOND: u64 = 1_000_000_000;
+/// delay before the first check of a close transaction, enough for it to be included in a block
+const CLOSE_RETRY_BASE_DELAY_SECS: u64 = 30;
+/// upper bound of the exponential backoff between the retries
+const CLOSE_RETRY_MAX_DELAY_SECS: u64 = 60 * 60;
+/// after this many attempts the close is considered stuck and needs the attention of an admin
+const CLOSE_STUCK_ATTEMPTS: u32 = 10;
+
+/// Akash deployment whose closure has not been confirmed on chain yet.
+///
+/// Until the deployment is closed, the AKT deposited in its escrow account stay locked.
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct PendingClose {
+    pub dseq: u64,
+    pub enqueued_at: TimestampNs,
+    /// number of close transactions sent so far
+    pub attempts: u32,
+    pub next_attempt_at: TimestampNs,
+    pub last_tx_hash: Option<String>,
+    pub last_error: Option<String>,
+    /// escrow account of the deployment right before the first close transaction,
+    /// used to compute the amount returned to the canister once the closure is confirmed
+    pub escrow_before_close: Option<EscrowSnapshot>,
+    /// set when the close is confirmed on chain but the escrow refund could not be credited,
+    /// the entry is then kept until an admin settles the refund manually
+    pub refund_owed_since: Option<TimestampNs>,
+    /// set while the creation transaction of the deployment may still be applied up to this height:
+    /// the deployment is closed if it appears on chain and dropped from the queue otherwise
+    pub awaiting_creation_until: Option<u64>,
+}
+
+impl PendingClose {
+    pub fn new(dseq: u64, escrow_before_close: Option<EscrowSnapshot>, now: TimestampNs) -> Self {
+        Self {
+            dseq,
+            enqueued_at: now,
+            attempts: 0,
+            next_attempt_at: now,
+            last_tx_hash: None,
+            last_error: None,
+            escrow_before_close,
+            refund_owed_since: None,
+            awaiting_creation_until: None,
+        }
+    }
+
+    pub fn awaiting_creation(dseq: u64, until_height: u64, now: TimestampNs) -> Self {
+        Self {
+            awaiting_creation_until: Some(until_height),
+            ..Self::new(dseq, None, now)
+        }
+    }
+
+    pub fn is_due(&self, now: TimestampNs) -> bool {
+        !self.is_refund_owed() && self.next_attempt_at <= now
+    }
+
+    pub fn is_refund_owed(&self) -> bool {
+        self.refund_owed_since.is_some()
+    }
+
+    /// Keeps the closed deployment in the queue, as its escrow refund has to be settled manually.
+    pub fn record_refund_owed(&mut self, error: String, now: TimestampNs) {
+        self.last_error = Some(error);
+        self.refund_owed_since = Some(now);
+    }
+
+    pub fn is_stuck(&self) -> bool {
+        self.attempts >= CLOSE_STUCK_ATTEMPTS
+    }
+
+    /// Records the outcome of a close transaction and schedules the next check.
+    pub fn record_attempt(&mut self, result: Result<String, String>, now: TimestampNs) {
+        self.attempts += 1;
+
+        match result {
+            Ok(tx_hash) => self.last_tx_hash = Some(tx_hash),
+            Err(e) => self.last_error = Some(e),
+        }
+
+        self.postpone(now);
+    }
+
+    /// Schedules the next check, doubling the delay at each attempt.
+    pub fn postpone(&mut self, now: TimestampNs) {
+        let delay_secs = CLOSE_RETRY_BASE_DELAY_SECS
+            .saturating_mul(2u64.saturating_pow(self.attempts.saturating_sub(1)))
+            .min(CLOSE_RETRY_MAX_DELAY_SECS);
+
+        self.next_attempt_at = now + delay_secs * NANOS_IN_SECOND;
+    }
+}
+
+impl Storable for PendingClose {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Amounts (in uakt) of the escrow account of a deployment at a given time.
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq)]
+pub struct EscrowSnapshot {
+    /// funds still available in the escrow
+    pub balance_uakt: f64,
+    /// funds paid to the providers so far
+    pub transferred_uakt: f64,
+}
+
+impl EscrowSnapshot {
+    /// Amount returned to the depositor between this snapshot and the one taken after the closure.
+    ///
+    /// On close, the escrow first pays the providers for the blocks not settled yet
+    /// and then returns the remaining balance to the depositor.
+    pub fn refunded_uakt(&self, after_close: &EscrowSnapshot) -> f64 {
+        let paid_on_close = after_close.transferred_uakt - self.transferred_uakt;
+        let refunded = self.balance_uakt - paid_on_close - after_close.balance_uakt;
+
+        refunded.max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refunded_uakt() {
+        let before_close = EscrowSnapshot {
+            balance_uakt: 5_000_000.0,
+            transferred_uakt: 1_000_000.0,
+        };
+        let after_close = EscrowSnapshot {
+            balance_uakt: 0.0,
+            transferred_uakt: 1_200_000.0,
+        };
+
+        assert_eq!(before_close.refunded_uakt(&after_close), 4_800_000.0);
+    }
+
+    #[test]
+    fn test_refunded_uakt_is_never_negative() {
+        let before_close = EscrowSnapshot {
+            balance_uakt: 100_000.0,
+            transferred_uakt: 1_000_000.0,
+        };
+        let after_close = EscrowSnapshot {
+            balance_uakt: 0.0,
+            transferred_uakt: 1_300_000.0,
+        };
+
+        assert_eq!(before_close.refunded_uakt(&after_close), 0.0);
+    }
+}
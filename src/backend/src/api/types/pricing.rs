@@ -0,0 +1,72 @@
+use candid::{CandidType, Deserialize};
+
+use crate::akash::sdl::SdlResourceRequest;
+
+const MILLIS_IN_CPU: f64 = 1_000.0;
+const BYTES_IN_GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Rates used to estimate the cost of a deployment on Akash.
+///
+/// Rates are expressed in uakt per block.
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq)]
+pub struct PricingConfig {
+    pub cpu_uakt_per_unit: f64,
+    pub memory_uakt_per_gib: f64,
+    pub storage_uakt_per_gib: f64,
+    pub gpu_uakt_per_unit: f64,
+    /// average time between two blocks on Akash
+    pub block_time_secs: f64,
+    /// runtime used when the deployment does not specify one
+    pub default_duration_secs: u64,
+    /// fraction added on top of the Akash cost to cover the orchestration, e.g. 0.1 for 10%
+    pub orchestration_margin: f64,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            cpu_uakt_per_unit: 100.0,
+            memory_uakt_per_gib: 50.0,
+            storage_uakt_per_gib: 10.0,
+            gpu_uakt_per_unit: 1_000.0,
+            block_time_secs: 6.0,
+            default_duration_secs: 24 * 60 * 60,
+            orchestration_margin: 0.1,
+        }
+    }
+}
+
+impl PricingConfig {
+    /// Estimated price per block of the requested resources.
+    ///
+    /// The bids cannot exceed the max price set in the SDL, so each request is capped to it.
+    pub fn uakt_per_block(&self, requests: &[SdlResourceRequest]) -> f64 {
+        requests
+            .iter()
+            .map(|request| {
+                let estimate = request.cpu_millis as f64 / MILLIS_IN_CPU * self.cpu_uakt_per_unit
+                    + request.memory_bytes / BYTES_IN_GIB * self.memory_uakt_per_gib
+                    + request.storage_bytes / BYTES_IN_GIB * self.storage_uakt_per_gib
+                    + request.gpu_units as f64 * self.gpu_uakt_per_unit;
+
+                estimate.min(request.max_price_uakt as f64) * request.count as f64
+            })
+            .sum()
+    }
+
+    /// Number of blocks produced during the given runtime.
+    pub fn blocks(&self, duration_secs: Option<u64>) -> u64 {
+        let duration_secs = duration_secs.unwrap_or(self.default_duration_secs);
+
+        (duration_secs as f64 / self.block_time_secs).ceil() as u64
+    }
+}
+
+/// Price of a deployment, as charged to the user.
+#[derive(CandidType, Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct DeploymentPrice {
+    /// amount deposited in the escrow of the deployment on Akash
+    pub escrow_deposit_uakt: u64,
+    /// escrow deposit plus the orchestration margin
+    pub akt: f64,
+}
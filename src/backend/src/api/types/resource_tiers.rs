@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+
+use crate::akash::sdl::sizes::{convert_cpu_resource_string, convert_resource_string};
+
+/// Resources assigned to the Small, Medium and Large sizes of the deployment params.
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ResourceTiers {
+    /// CPU units, e.g. "0.5" or "500m"
+    pub cpu: ResourceTier,
+    /// memory size, e.g. "512Mi"
+    pub memory: ResourceTier,
+    /// storage size, e.g. "1Gi"
+    pub storage: ResourceTier,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ResourceTier {
+    pub small: String,
+    pub medium: String,
+    pub large: String,
+}
+
+impl Default for ResourceTiers {
+    fn default() -> Self {
+        Self {
+            cpu: ResourceTier {
+                small: "0.5".to_string(),
+                medium: "1".to_string(),
+                large: "2".to_string(),
+            },
+            memory: ResourceTier {
+                small: "512Mi".to_string(),
+                medium: "1Gi".to_string(),
+                large: "4Gi".to_string(),
+            },
+            storage: ResourceTier {
+                small: "512Mi".to_string(),
+                medium: "2Gi".to_string(),
+                large: "8Gi".to_string(),
+            },
+        }
+    }
+}
+
+impl ResourceTiers {
+    pub fn validate(&self) -> Result<(), String> {
+        self.cpu.validate("CPU", |units| {
+            convert_cpu_resource_string(units).map(|millis| millis as f64)
+        })?;
+        self.memory.validate("memory", convert_resource_string)?;
+        self.storage.validate("storage", convert_resource_string)?;
+
+        Ok(())
+    }
+}
+
+impl ResourceTier {
+    fn validate(
+        &self,
+        resource: &str,
+        convert: impl Fn(&str) -> Result<f64, String>,
+    ) -> Result<(), String> {
+        let small = convert(&self.small)?;
+        let medium = convert(&self.medium)?;
+        let large = convert(&self.large)?;
+
+        if small <= 0.0 {
+            return Err(format!("{} tiers must be greater than 0", resource));
+        }
+
+        if small > medium || medium > large {
+            return Err(format!(
+                "{} tiers must be sorted from Small to Large",
+                resource
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Storable for ResourceTiers {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -3,15 +3,34 @@ use serde::{Deserialize, Serialize};
 
 use super::DeploymentState;
 
-#[derive(Debug, CandidType, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, CandidType, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DeploymentUpdateWsMessage {
     id: String,
     update: DeploymentState,
+    /// manifest to send to the providers, only set when the deployment has been updated
+    manifest_sorted_json: Option<String>,
+    /// message that requires the user's attention, e.g. when the escrow cannot be topped up
+    alert: Option<String>,
 }
 
 impl DeploymentUpdateWsMessage {
     pub fn new(id: String, update: DeploymentState) -> Self {
-        Self { id, update }
+        Self {
+            id,
+            update,
+            manifest_sorted_json: None,
+            alert: None,
+        }
+    }
+
+    pub fn with_manifest(mut self, manifest_sorted_json: String) -> Self {
+        self.manifest_sorted_json = Some(manifest_sorted_json);
+        self
+    }
+
+    pub fn with_alert(mut self, alert: String) -> Self {
+        self.alert = Some(alert);
+        self
     }
 
     pub fn candid_serialize(&self) -> Vec<u8> {
@@ -6,7 +6,7 @@ use ic_cdk::api::management_canister::ecdsa::{
 };
 use serde::Deserialize;
 
-#[derive(CandidType, Clone, Deserialize)]
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
 pub enum EcdsaKeyIds {
     #[allow(unused)]
     TestKeyLocalDevelopment,
@@ -30,10 +30,14 @@ impl EcdsaKeyIds {
     }
 }
 
-pub async fn get_public_key(key: &EcdsaKeyIds) -> Result<PublicKey, String> {
+/// The derivation path selects the key derived from the canister's key, the canister's own key being derived with an empty path.
+pub async fn get_public_key(
+    key: &EcdsaKeyIds,
+    derivation_path: Vec<Vec<u8>>,
+) -> Result<PublicKey, String> {
     let request = EcdsaPublicKeyArgument {
         canister_id: None,
-        derivation_path: vec![],
+        derivation_path,
         key_id: key.to_key_id(),
     };
 
@@ -47,10 +51,14 @@ pub async fn get_public_key(key: &EcdsaKeyIds) -> Result<PublicKey, String> {
     .map_err(|e| e.to_string())
 }
 
-pub async fn sign(message_hash: Vec<u8>, key: &EcdsaKeyIds) -> Result<Vec<u8>, String> {
+pub async fn sign(
+    message_hash: Vec<u8>,
+    key: &EcdsaKeyIds,
+    derivation_path: Vec<Vec<u8>>,
+) -> Result<Vec<u8>, String> {
     let request = SignWithEcdsaArgument {
         message_hash,
-        derivation_path: vec![],
+        derivation_path,
         key_id: key.to_key_id(),
     };
 
@@ -1,6 +1,11 @@
 //! Serialize/deserialize Timestamp type from and into string:
 
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use core::fmt;
+
+use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use time::{
+    format_description::well_known::Rfc3339 as Rfc3339Format, macros::offset, OffsetDateTime,
+};
 
 use crate::{google::protobuf::Timestamp, prelude::*};
 
@@ -23,40 +28,35 @@ impl From<Rfc3339> for Timestamp {
 }
 
 /// Deserialize string into Timestamp
-pub fn deserialize<'de, D>(_deserializer: D) -> Result<Timestamp, D::Error>
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Timestamp, D::Error>
 where
     D: Deserializer<'de>,
 {
-    // let value_string = String::deserialize(deserializer)?;
-    // let t = OffsetDateTime::parse(&value_string, &Rfc3339Format).map_err(D::Error::custom)?;
-    // let t = t.to_offset(offset!(UTC));
-    // if !matches!(t.year(), 1..=9999) {
-    //     return Err(D::Error::custom("date is out of range"));
-    // }
-    // let seconds = t.unix_timestamp();
-    // // Safe to convert to i32 because .nanosecond()
-    // // is guaranteed to return a value in 0..1_000_000_000 range.
-    // let nanos = t.nanosecond() as i32;
-    // Ok(Timestamp { seconds, nanos })
-    Ok(Timestamp {
-        seconds: 123456,
-        nanos: 789,
-    })
+    let value_string = String::deserialize(deserializer)?;
+    let t = OffsetDateTime::parse(&value_string, &Rfc3339Format).map_err(D::Error::custom)?;
+    let t = t.to_offset(offset!(UTC));
+    if !matches!(t.year(), 1..=9999) {
+        return Err(D::Error::custom("date is out of range"));
+    }
+    let seconds = t.unix_timestamp();
+    // Safe to convert to i32 because .nanosecond()
+    // is guaranteed to return a value in 0..1_000_000_000 range.
+    let nanos = t.nanosecond() as i32;
+    Ok(Timestamp { seconds, nanos })
 }
 
 /// Serialize from Timestamp into string
-pub fn serialize<S>(_value: &Timestamp, serializer: S) -> Result<S::Ok, S::Error>
+pub fn serialize<S>(value: &Timestamp, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    // if value.nanos < 0 || value.nanos > 999_999_999 {
-    //     return Err(S::Error::custom("invalid nanoseconds in time"));
-    // }
-    // let total_nanos = value.seconds as i128 * 1_000_000_000 + value.nanos as i128;
-    // let datetime = OffsetDateTime::from_unix_timestamp_nanos(total_nanos)
-    //     .map_err(|_| S::Error::custom("invalid time"))?;
-    // to_rfc3339_nanos(datetime).serialize(serializer)
-    String::from("2022-12-01T15:30:45.123456789Z").serialize(serializer)
+    if value.nanos < 0 || value.nanos > 999_999_999 {
+        return Err(S::Error::custom("invalid nanoseconds in time"));
+    }
+    let total_nanos = value.seconds as i128 * 1_000_000_000 + value.nanos as i128;
+    let datetime = OffsetDateTime::from_unix_timestamp_nanos(total_nanos)
+        .map_err(|_| S::Error::custom("invalid time"))?;
+    to_rfc3339_nanos(datetime).serialize(serializer)
 }
 
 /// Serialization helper for converting an [`OffsetDateTime`] object to a string.
@@ -64,18 +64,17 @@ where
 /// This reproduces the behavior of Go's `time.RFC3339Nano` format,
 /// ie. a RFC3339 date-time with left-padded subsecond digits without
 ///     trailing zeros and no trailing dot.
-pub fn to_rfc3339_nanos() -> String {
+pub fn to_rfc3339_nanos(t: OffsetDateTime) -> String {
     // Can't use OffsetDateTime::format because the feature enabling it
     // currently requires std (https://github.com/time-rs/time/issues/400)
 
     // Preallocate enough string capacity to fit the shortest possible form,
     // yyyy-mm-ddThh:mm:ssZ
-    // let mut buf = String::with_capacity(20);
+    let mut buf = String::with_capacity(20);
 
-    // fmt_as_rfc3339_nanos(t, &mut buf).unwrap();
+    fmt_as_rfc3339_nanos(t, &mut buf).unwrap();
 
-    // buf
-    String::from("2022-12-01T15:30:45.123456789Z")
+    buf
 }
 
 /// Helper for formatting an [`OffsetDateTime`] value.
@@ -89,41 +88,41 @@ pub fn to_rfc3339_nanos() -> String {
 ///
 /// [`Display`]: core::fmt::Display
 /// [`Debug`]: core::fmt::Debug
-// pub fn fmt_as_rfc3339_nanos(t: OffsetDateTime, f: &mut impl fmt::Write) -> fmt::Result {
-//     let t = t.to_offset(offset!(UTC));
-//     let nanos = t.nanosecond();
-//     if nanos == 0 {
-//         write!(
-//             f,
-//             "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z",
-//             year = t.year(),
-//             month = t.month() as u8,
-//             day = t.day(),
-//             hour = t.hour(),
-//             minute = t.minute(),
-//             second = t.second(),
-//         )
-//     } else {
-//         let mut secfrac = nanos;
-//         let mut secfrac_width = 9;
-//         while secfrac % 10 == 0 {
-//             secfrac /= 10;
-//             secfrac_width -= 1;
-//         }
-//         write!(
-//             f,
-//             "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{secfrac:0sfw$}Z",
-//             year = t.year(),
-//             month = t.month() as u8,
-//             day = t.day(),
-//             hour = t.hour(),
-//             minute = t.minute(),
-//             second = t.second(),
-//             secfrac = secfrac,
-//             sfw = secfrac_width,
-//         )
-//     }
-// }
+pub fn fmt_as_rfc3339_nanos(t: OffsetDateTime, f: &mut impl fmt::Write) -> fmt::Result {
+    let t = t.to_offset(offset!(UTC));
+    let nanos = t.nanosecond();
+    if nanos == 0 {
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z",
+            year = t.year(),
+            month = t.month() as u8,
+            day = t.day(),
+            hour = t.hour(),
+            minute = t.minute(),
+            second = t.second(),
+        )
+    } else {
+        let mut secfrac = nanos;
+        let mut secfrac_width = 9;
+        while secfrac % 10 == 0 {
+            secfrac /= 10;
+            secfrac_width -= 1;
+        }
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{secfrac:0sfw$}Z",
+            year = t.year(),
+            month = t.month() as u8,
+            day = t.day(),
+            hour = t.hour(),
+            minute = t.minute(),
+            second = t.second(),
+            secfrac = secfrac,
+            sfw = secfrac_width,
+        )
+    }
+}
 
 #[allow(warnings)]
 #[cfg(test)]
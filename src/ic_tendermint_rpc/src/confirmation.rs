@@ -0,0 +1,307 @@
+use std::time::Duration;
+
+use ic_cdk::api::management_canister::http_request::{HttpHeader, HttpMethod, TransformContext};
+use subtle_encoding::base64;
+use tendermint::{abci, hash::Algorithm, Hash};
+use utils::make_http_request;
+
+use crate::{
+    endpoints::tx::{Request as TxRequest, Response as TxResponse},
+    latest_block,
+    request::{Request, Wrapper},
+    response::Response,
+    timer::sleep,
+    MAX_RESPONSE_SIZE, REQUEST_SIZE,
+};
+
+/// Result of the execution of a transaction included in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecTxResult {
+    /// hex encoded hash of the transaction
+    pub hash: String,
+    /// height of the block that includes the transaction
+    pub height: u64,
+    /// 0 if the transaction has been executed successfully
+    pub code: u32,
+    /// namespace of the error code
+    pub codespace: String,
+    pub log: String,
+    pub gas_wanted: i64,
+    pub gas_used: i64,
+    pub events: Vec<TxEvent>,
+}
+
+impl ExecTxResult {
+    pub fn is_ok(&self) -> bool {
+        self.code == 0
+    }
+
+    /// Fails with the code and the log of the transaction if its execution failed.
+    pub fn into_result(self) -> Result<Self, String> {
+        if self.is_ok() {
+            return Ok(self);
+        }
+
+        Err(format!(
+            "tx {} failed with code {} ({}): {}",
+            self.hash, self.code, self.codespace, self.log
+        ))
+    }
+}
+
+impl From<TxResponse> for ExecTxResult {
+    fn from(response: TxResponse) -> Self {
+        let result = response.tx_result;
+
+        Self {
+            hash: response.hash.to_string(),
+            height: response.height.value(),
+            code: result.code.value(),
+            codespace: result.codespace,
+            log: result.log,
+            gas_wanted: result.gas_wanted,
+            gas_used: result.gas_used,
+            events: result.events.into_iter().map(TxEvent::from).collect(),
+        }
+    }
+}
+
+/// Event emitted while executing a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxEvent {
+    pub kind: String,
+    /// (key, value) pairs
+    pub attributes: Vec<(String, String)>,
+}
+
+impl TxEvent {
+    /// Value of the first attribute with the given key.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl From<abci::Event> for TxEvent {
+    fn from(event: abci::Event) -> Self {
+        Self {
+            kind: event.kind,
+            attributes: event
+                .attributes
+                .into_iter()
+                .map(|attribute| decode_attribute(attribute.key, attribute.value))
+                .collect(),
+        }
+    }
+}
+
+/// Nodes running Tendermint 0.34 return the keys and values of the event attributes base64 encoded,
+/// while newer versions return them as plain strings.
+///
+/// Keys are short ASCII identifiers, so a key that decodes to printable ASCII is base64 encoded.
+fn decode_attribute(key: String, value: String) -> (String, String) {
+    let decode = |s: &str| {
+        base64::decode(s)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+    };
+
+    match decode(&key) {
+        Some(decoded_key)
+            if !decoded_key.is_empty() && decoded_key.bytes().all(|b| b.is_ascii_graphic()) =>
+        {
+            let decoded_value = decode(&value).unwrap_or(value);
+            (decoded_key, decoded_value)
+        }
+        _ => (key, value),
+    }
+}
+
+/// How [wait_for_tx] waits for a transaction to be included in a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxPolling {
+    /// queries the transaction again as soon as the previous query returns,
+    /// each HTTPS outcall already takes a few seconds
+    ///
+    /// The task never leaves the message it started in, so this is safe to use while handling a call.
+    Outcalls { max_queries: u32 },
+    /// waits for the given interval on a timer before querying the transaction again
+    ///
+    /// The task is resumed by the timer, so this can only be used by tasks spawned from timers,
+    /// which do not have to reply to a caller.
+    Timer {
+        interval: Duration,
+        max_queries: u32,
+    },
+}
+
+impl TxPolling {
+    fn max_queries(&self) -> u32 {
+        match self {
+            TxPolling::Outcalls { max_queries } | TxPolling::Timer { max_queries, .. } => {
+                *max_queries
+            }
+        }
+    }
+}
+
+/// Why the result of a transaction could not be fetched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TxQueryError {
+    /// the outcall failed or the replicas did not agree on the response,
+    /// e.g. because the transaction was included in a block while they were querying it
+    Transport(String),
+    /// the hash is invalid or the response is not a transaction result
+    Definitive(String),
+}
+
+impl TxQueryError {
+    fn into_message(self) -> String {
+        match self {
+            TxQueryError::Transport(e) | TxQueryError::Definitive(e) => e,
+        }
+    }
+}
+
+/// Fetches the result of the transaction, `None` if it has not been included in a block yet.
+pub async fn tx(url: String, hash_hex: String) -> Result<Option<ExecTxResult>, String> {
+    query_tx(url, hash_hex)
+        .await
+        .map_err(TxQueryError::into_message)
+}
+
+async fn query_tx(url: String, hash_hex: String) -> Result<Option<ExecTxResult>, TxQueryError> {
+    let hash = Hash::from_hex_upper(Algorithm::Sha256, &hash_hex.to_uppercase())
+        .map_err(|e| TxQueryError::Definitive(format!("invalid tx hash {}: {}", hash_hex, e)))?;
+    let request = TxRequest::new(hash, false);
+    let request_body = Wrapper::new(request).await.into_json().into_bytes();
+
+    let request_headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
+
+    let response = make_http_request(
+        url,
+        HttpMethod::POST,
+        Some(request_body),
+        request_headers,
+        Some(TransformContext::from_name(
+            "tx_transform".to_string(),
+            vec![],
+        )),
+        REQUEST_SIZE,
+        MAX_RESPONSE_SIZE,
+    )
+    .await
+    .map_err(TxQueryError::Transport)?;
+
+    match <TxRequest as Request>::Response::from_string(response.body) {
+        Ok(response) => Ok(Some(ExecTxResult::from(response))),
+        // the node only knows about the transactions included in a block
+        Err(e) if e.contains("not found") => Ok(None),
+        Err(e) => Err(TxQueryError::Definitive(e)),
+    }
+}
+
+/// Outcome of waiting for a transaction with [wait_for_tx].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxStatus {
+    /// the transaction has been included in a block,
+    /// its result has to be checked with [ExecTxResult::into_result]
+    Included(ExecTxResult),
+    /// the chain is past the timeout height of the transaction without including it,
+    /// so it will never be included
+    Expired,
+    /// the polling ended before the transaction was included or expired,
+    /// so it may still be included
+    Unknown(String),
+}
+
+/// Polls the transaction until it is included in a block or the chain is past its timeout height.
+///
+/// A `timeout_height` of 0 means the transaction never expires, so it is polled until it is included.
+/// Failed outcalls are retried like a transaction not included in a block yet.
+pub async fn wait_for_tx(
+    url: String,
+    hash_hex: String,
+    timeout_height: u64,
+    polling: TxPolling,
+) -> TxStatus {
+    let mut last_error = None;
+
+    for query in 0..polling.max_queries() {
+        if query > 0 {
+            if let TxPolling::Timer { interval, .. } = polling {
+                sleep(interval).await;
+            }
+        }
+
+        // the height is fetched before the transaction, so that a transaction
+        // not found afterwards can no longer be included once the height is past its timeout
+        let height = if timeout_height > 0 {
+            match latest_block_height(url.clone()).await {
+                Ok(height) => Some(height),
+                Err(e) => {
+                    last_error = Some(e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        match query_tx(url.clone(), hash_hex.clone()).await {
+            Ok(Some(result)) => return TxStatus::Included(result),
+            Ok(None) if height.is_some_and(|height| height > timeout_height) => {
+                return TxStatus::Expired
+            }
+            Ok(None) => {}
+            Err(TxQueryError::Transport(e)) => last_error = Some(e),
+            Err(e @ TxQueryError::Definitive(_)) => return TxStatus::Unknown(e.into_message()),
+        }
+    }
+
+    TxStatus::Unknown(format!(
+        "tx {} neither included in a block nor expired after {} queries (last error: {})",
+        hash_hex,
+        polling.max_queries(),
+        last_error.as_deref().unwrap_or("none")
+    ))
+}
+
+async fn latest_block_height(url: String) -> Result<u64, String> {
+    latest_block(url)
+        .await
+        .map(|block| block.block.header.height.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_attribute() {
+        // Tendermint 0.34
+        assert_eq!(
+            decode_attribute(String::from("ZHNlcQ=="), String::from("MTIzNDU=")),
+            (String::from("dseq"), String::from("12345"))
+        );
+        assert_eq!(
+            decode_attribute(String::from("YWN0aW9u"), String::from("Y3JlYXRlLWxlYXNl")),
+            (String::from("action"), String::from("create-lease"))
+        );
+
+        // newer versions
+        assert_eq!(
+            decode_attribute(String::from("dseq"), String::from("12345")),
+            (String::from("dseq"), String::from("12345"))
+        );
+        assert_eq!(
+            decode_attribute(String::from("provider"), String::from("akash1abc")),
+            (String::from("provider"), String::from("akash1abc"))
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! `/block` endpoint JSON-RPC wrapper
 
 use serde::{Deserialize, Serialize};
-use tendermint::block::{self, Block};
+use tendermint::block;
 
 use crate::{
     method::Method,
@@ -34,7 +34,62 @@ pub struct Response {
     pub block_id: block::Id,
 
     /// Block data
-    pub block: Block,
+    pub block: BlockHeader,
 }
 
 impl crate::Response for Response {}
+
+/// Only the header of the block is kept, see [header_only]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlockHeader {
+    /// Block header
+    pub header: block::Header,
+}
+
+/// Drops the transactions, evidence and last commit from the body of a `/block` response.
+///
+/// They make the response big and are not needed to know the height and time of the block.
+/// The body is returned unchanged if it is not a valid JSON-RPC response, e.g. an error.
+pub fn header_only(body: &[u8]) -> Vec<u8> {
+    let Ok(mut response) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return body.to_vec();
+    };
+
+    if let Some(block) = response
+        .get_mut("result")
+        .and_then(|result| result.get_mut("block"))
+        .and_then(|block| block.as_object_mut())
+    {
+        block.retain(|key, _| key == "header");
+    }
+
+    serde_json::to_vec(&response).unwrap_or_else(|_| body.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response as _;
+
+    const HASH: &str = "6A2B58A3F4B5A0D4E3C1C1F4D0F5E2C8B6B1D2E3F4A5B6C7D8E9F0A1B2C3D4E5";
+
+    #[test]
+    fn parses_header_of_latest_block() {
+        let parts = format!(r#"{{"hash":"{HASH}","parts":{{"total":1,"hash":"{HASH}"}}}}"#);
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":{{"block_id":{parts},"block":{{
+                "header":{{"version":{{"block":"11","app":"0"}},"chain_id":"sandbox-01","height":"1234567",
+                "time":"2024-02-20T10:00:00.123456789Z","last_block_id":{parts},
+                "last_commit_hash":"{HASH}","data_hash":"{HASH}","validators_hash":"{HASH}",
+                "next_validators_hash":"{HASH}","consensus_hash":"{HASH}","app_hash":"{HASH}",
+                "last_results_hash":"{HASH}","evidence_hash":"{HASH}",
+                "proposer_address":"8F2D6C5A1B3E4D7F9A0B1C2D3E4F5A6B7C8D9E0F"}},
+                "data":{{"txs":["not a transaction"]}},"evidence":{{"evidence":[]}},"last_commit":null}}}}}}"#
+        );
+
+        let response = Response::from_string(header_only(body.as_bytes())).unwrap();
+
+        assert_eq!(response.block.header.height.value(), 1_234_567);
+        assert_eq!(response.block.header.chain_id.as_str(), "sandbox-01");
+    }
+}
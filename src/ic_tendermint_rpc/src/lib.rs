@@ -3,8 +3,9 @@ use ic_cdk::{
     api::management_canister::http_request::{
         HttpHeader, HttpMethod, HttpResponse, TransformArgs, TransformContext,
     },
-    print, query,
+    query,
 };
+mod confirmation;
 mod endpoints;
 mod id;
 mod method;
@@ -12,30 +13,57 @@ mod request;
 mod response;
 mod response_error;
 mod serializers;
+mod timer;
 mod version;
 
+pub use confirmation::{tx, wait_for_tx, ExecTxResult, TxEvent, TxPolling, TxStatus};
 use endpoints::{
-    abci_info::Request as AbciInfoRequest, abci_query::Request as AbciQueryRequest,
-    tx::Request as TxRequest, tx_sync::Request as TxSyncRequest,
+    abci_info::Request as AbciInfoRequest,
+    abci_query::Request as AbciQueryRequest,
+    block::{self, Request as BlockRequest},
+    tx_sync::Request as TxSyncRequest,
 };
 use request::{Request, Wrapper};
 use response::Response;
-use tendermint::{block::Height, hash::Algorithm, Hash};
+use tendermint::block::Height;
 use utils::{make_http_request, sha256};
 
 /// assume requests are at most 5kb
 const REQUEST_SIZE: u128 = 5_000;
 /// refuse responses that return more than 15kb
 const MAX_RESPONSE_SIZE: u64 = 15_000;
+/// blocks include their transactions, which are dropped by the transform only after the response is received
+const MAX_BLOCK_RESPONSE_SIZE: u64 = 200_000;
+
+/// Fetches the header of the latest block, see [block::header_only].
+///
+/// The replicas agree on the response only if no block is produced while they make the request.
+pub async fn latest_block(url: String) -> Result<<BlockRequest as Request>::Response, String> {
+    let request_body = Wrapper::new(BlockRequest::default())
+        .await
+        .into_json()
+        .into_bytes();
 
-// TODO: fix deserialization
-// pub async fn latest_block(url: String) -> Result<<BlockRequest as Request>::Response, String> {
-//     let request = BlockRequest::default();
-//     let request_body = Wrapper::new(request).await.into_json().into_bytes();
+    let request_headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
 
-//     let response = make_http_request(url, HttpMethod::GET, Some(request_body), None).await?;
-//     <BlockRequest as Request>::Response::from_string(&response.body)
-// }
+    let response = make_http_request(
+        url,
+        HttpMethod::POST,
+        Some(request_body),
+        request_headers,
+        Some(TransformContext::from_name(
+            "block_transform".to_string(),
+            vec![],
+        )),
+        REQUEST_SIZE,
+        MAX_BLOCK_RESPONSE_SIZE,
+    )
+    .await?;
+    <BlockRequest as Request>::Response::from_string(response.body)
+}
 
 pub async fn abci_info(url: String) -> Result<<AbciInfoRequest as Request>::Response, String> {
     let request_body = Wrapper::new(AbciInfoRequest).await.into_json().into_bytes();
@@ -45,12 +73,16 @@ pub async fn abci_info(url: String) -> Result<<AbciInfoRequest as Request>::Resp
         value: "application/json".to_string(),
     }];
 
+    // the replicas agree on the response only if no block is produced while they make the request
     let response = make_http_request(
         url,
         HttpMethod::GET,
         Some(request_body),
         request_headers,
-        None,
+        Some(TransformContext::from_name(
+            "abci_transform".to_string(),
+            vec![],
+        )),
         REQUEST_SIZE,
         MAX_RESPONSE_SIZE,
     )
@@ -94,39 +126,6 @@ pub async fn abci_query(
     <AbciQueryRequest as Request>::Response::from_string(response.body)
 }
 
-pub async fn check_tx(url: String, hash_hex: String) -> Result<(), String> {
-    let request = TxRequest::new(
-        Hash::from_hex_upper(Algorithm::Sha256, &hash_hex.to_uppercase()).unwrap(),
-        true,
-    );
-    let request_body = Wrapper::new(request).await.into_json().into_bytes();
-
-    let request_headers = vec![HttpHeader {
-        name: "Content-Type".to_string(),
-        value: "application/json".to_string(),
-    }];
-
-    let response = make_http_request(
-        url,
-        HttpMethod::GET,
-        Some(request_body),
-        request_headers,
-        None,
-        REQUEST_SIZE,
-        MAX_RESPONSE_SIZE,
-    )
-    .await?;
-    let response_body = <TxRequest as Request>::Response::from_string(response.body);
-    if let Ok(response_body) = response_body {
-        print(format!(
-            "[check_tx] response: {:?}",
-            response_body.tx_result
-        ));
-    }
-
-    Ok(())
-}
-
 pub async fn broadcast_tx_sync(
     is_mainnet: bool,
     url: String,
@@ -198,6 +197,26 @@ fn abci_transform(raw: TransformArgs) -> HttpResponse {
     }
 }
 
+#[query]
+fn block_transform(raw: TransformArgs) -> HttpResponse {
+    // the body is identical if no block is produced while the replicas make the request
+    HttpResponse {
+        status: raw.response.status.clone(),
+        body: block::header_only(&raw.response.body),
+        headers: vec![],
+    }
+}
+
+#[query]
+fn tx_transform(raw: TransformArgs) -> HttpResponse {
+    // the transaction is stored in the block, therefore the body of the responses is identical
+    HttpResponse {
+        status: raw.response.status.clone(),
+        body: raw.response.body.clone(),
+        headers: vec![],
+    }
+}
+
 #[query]
 fn broadcast_tx_sync_transform(raw: TransformArgs) -> HttpResponse {
     // the response to the first request should be accepted and return 'Ok' while the others should be 'Err' and contain "tx already exists in cache"
@@ -0,0 +1,56 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+#[derive(Default)]
+struct SleepState {
+    elapsed: bool,
+    waker: Option<Waker>,
+}
+
+/// Future that completes when the one-shot timer set by [sleep] fires.
+struct Sleep {
+    state: Rc<RefCell<SleepState>>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        if state.elapsed {
+            return Poll::Ready(());
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Waits for the given duration using a one-shot timer.
+///
+/// The task is resumed from the timer, i.e. in a different message than the one that started it,
+/// so this must only be awaited by tasks that do not have to reply to a caller.
+pub async fn sleep(duration: Duration) {
+    let state = Rc::new(RefCell::new(SleepState::default()));
+
+    let timer_state = Rc::clone(&state);
+    ic_cdk_timers::set_timer(duration, move || {
+        let waker = {
+            let mut state = timer_state.borrow_mut();
+            state.elapsed = true;
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+
+    Sleep { state }.await
+}
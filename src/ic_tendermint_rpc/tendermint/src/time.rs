@@ -114,8 +114,7 @@ impl Time {
 
     /// Return an RFC 3339 and ISO 8601 date and time string with subseconds (if nonzero) and Z.
     pub fn to_rfc3339(&self) -> String {
-        // timestamp::to_rfc3339_nanos(self.0.assume_utc())
-        timestamp::to_rfc3339_nanos()
+        timestamp::to_rfc3339_nanos(self.0.assume_utc())
     }
 
     /// Return a Unix timestamp in seconds.